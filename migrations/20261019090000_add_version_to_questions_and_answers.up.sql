@@ -0,0 +1,5 @@
+ALTER TABLE questions
+ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
+
+ALTER TABLE answers
+ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
@@ -0,0 +1,2 @@
+ALTER TABLE answers DROP COLUMN content_html;
+ALTER TABLE questions DROP COLUMN content_html;
@@ -0,0 +1,3 @@
+-- Rendered Markdown; existing rows are filled in by the application after migrating.
+ALTER TABLE questions ADD COLUMN content_html TEXT NOT NULL DEFAULT '';
+ALTER TABLE answers ADD COLUMN content_html TEXT NOT NULL DEFAULT '';
@@ -0,0 +1,14 @@
+CREATE TABLE IF NOT EXISTS attachments (
+    id uuid PRIMARY KEY,
+    question_id uuid REFERENCES questions ON DELETE CASCADE,
+    answer_id uuid REFERENCES answers ON DELETE CASCADE,
+    user_id uuid NOT NULL,
+    filename TEXT NOT NULL,
+    content_type TEXT NOT NULL,
+    size BIGINT NOT NULL,
+    created_on TIMESTAMP NOT NULL DEFAULT NOW(),
+    CHECK ((question_id IS NULL) <> (answer_id IS NULL))
+);
+
+CREATE INDEX attachments_question_id_idx ON attachments (question_id);
+CREATE INDEX attachments_answer_id_idx ON attachments (answer_id);
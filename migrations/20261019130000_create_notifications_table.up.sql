@@ -0,0 +1,20 @@
+CREATE TYPE notification_kind AS ENUM ('answer');
+
+CREATE TABLE IF NOT EXISTS notifications (
+    id uuid PRIMARY KEY,
+    user_id uuid NOT NULL,
+    kind notification_kind NOT NULL,
+    question_id uuid NOT NULL REFERENCES questions ON DELETE CASCADE,
+    answer_id uuid REFERENCES answers ON DELETE CASCADE,
+    actor_id uuid,
+    read_on TIMESTAMP,
+    created_on TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX notifications_user_id_idx ON notifications (user_id, created_on DESC);
+
+-- One row per user who changed a default; one column per notification kind.
+CREATE TABLE IF NOT EXISTS notification_preferences (
+    user_id uuid PRIMARY KEY,
+    answer BOOLEAN NOT NULL DEFAULT TRUE
+);
@@ -0,0 +1,30 @@
+CREATE TABLE IF NOT EXISTS webhooks (
+    id uuid PRIMARY KEY,
+    url TEXT NOT NULL,
+    secret TEXT NOT NULL,
+    events TEXT[] NOT NULL,
+    created_by uuid,
+    created_on TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE TYPE delivery_status AS ENUM ('pending', 'delivered', 'failed');
+
+-- Outbox and delivery log in one: rows are written in the transaction of the
+-- change they announce and updated by the worker after every attempt.
+CREATE TABLE IF NOT EXISTS webhook_deliveries (
+    id uuid PRIMARY KEY,
+    webhook_id uuid NOT NULL REFERENCES webhooks ON DELETE CASCADE,
+    event TEXT NOT NULL,
+    payload TEXT NOT NULL,
+    status delivery_status NOT NULL DEFAULT 'pending',
+    attempts INTEGER NOT NULL DEFAULT 0,
+    response_status INTEGER,
+    error TEXT,
+    next_attempt_on TIMESTAMP NOT NULL DEFAULT NOW(),
+    created_on TIMESTAMP NOT NULL DEFAULT NOW(),
+    delivered_on TIMESTAMP
+);
+
+CREATE INDEX webhook_deliveries_due_idx ON webhook_deliveries (next_attempt_on)
+    WHERE status = 'pending';
+CREATE INDEX webhook_deliveries_webhook_id_idx ON webhook_deliveries (webhook_id, created_on DESC);
@@ -0,0 +1,7 @@
+DROP INDEX IF EXISTS answers_user_id_created_on_idx;
+DROP INDEX IF EXISTS answers_question_id_created_on_idx;
+DROP INDEX IF EXISTS questions_tags_idx;
+DROP INDEX IF EXISTS questions_user_id_created_on_idx;
+DROP TABLE IF EXISTS user_follows;
+DROP TABLE IF EXISTS tag_follows;
+DROP TABLE IF EXISTS question_follows;
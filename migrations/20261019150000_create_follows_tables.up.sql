@@ -0,0 +1,27 @@
+CREATE TABLE IF NOT EXISTS question_follows (
+    user_id uuid NOT NULL REFERENCES users ON DELETE CASCADE,
+    question_id uuid NOT NULL REFERENCES questions ON DELETE CASCADE,
+    created_on TIMESTAMP NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_id, question_id)
+);
+
+CREATE TABLE IF NOT EXISTS tag_follows (
+    user_id uuid NOT NULL REFERENCES users ON DELETE CASCADE,
+    tag TEXT NOT NULL,
+    created_on TIMESTAMP NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_id, tag)
+);
+
+CREATE TABLE IF NOT EXISTS user_follows (
+    user_id uuid NOT NULL REFERENCES users ON DELETE CASCADE,
+    followed_id uuid NOT NULL REFERENCES users ON DELETE CASCADE,
+    created_on TIMESTAMP NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_id, followed_id),
+    CHECK (user_id <> followed_id)
+);
+
+-- The feed reads questions and answers newest first per author and question.
+CREATE INDEX IF NOT EXISTS questions_user_id_created_on_idx ON questions (user_id, created_on DESC);
+CREATE INDEX IF NOT EXISTS questions_tags_idx ON questions USING GIN (tags);
+CREATE INDEX IF NOT EXISTS answers_question_id_created_on_idx ON answers (question_id, created_on DESC);
+CREATE INDEX IF NOT EXISTS answers_user_id_created_on_idx ON answers (user_id, created_on DESC);
@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS bookmarks (
+    user_id uuid NOT NULL REFERENCES users ON DELETE CASCADE,
+    question_id uuid NOT NULL REFERENCES questions ON DELETE CASCADE,
+    created_on TIMESTAMP NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_id, question_id)
+);
+
+CREATE INDEX bookmarks_user_id_created_on_idx ON bookmarks (user_id, created_on DESC);
@@ -0,0 +1,6 @@
+DROP TABLE IF EXISTS user_badges;
+DROP TYPE IF EXISTS badge_kind;
+DROP TABLE IF EXISTS reputation_events;
+DROP TYPE IF EXISTS reputation_reason;
+ALTER TABLE questions DROP COLUMN IF EXISTS accepted_answer_id;
+DROP TABLE IF EXISTS votes;
@@ -0,0 +1,52 @@
+CREATE TABLE IF NOT EXISTS votes (
+    user_id uuid NOT NULL REFERENCES users ON DELETE CASCADE,
+    question_id uuid REFERENCES questions ON DELETE CASCADE,
+    answer_id uuid REFERENCES answers ON DELETE CASCADE,
+    value SMALLINT NOT NULL CHECK (value IN (-1, 1)),
+    created_on TIMESTAMP NOT NULL DEFAULT NOW(),
+    CHECK ((question_id IS NULL) <> (answer_id IS NULL))
+);
+
+CREATE UNIQUE INDEX votes_user_id_question_id_idx ON votes (user_id, question_id)
+    WHERE question_id IS NOT NULL;
+CREATE UNIQUE INDEX votes_user_id_answer_id_idx ON votes (user_id, answer_id)
+    WHERE answer_id IS NOT NULL;
+CREATE INDEX votes_question_id_idx ON votes (question_id) WHERE question_id IS NOT NULL;
+CREATE INDEX votes_answer_id_idx ON votes (answer_id) WHERE answer_id IS NOT NULL;
+
+ALTER TABLE questions
+ADD COLUMN accepted_answer_id uuid REFERENCES answers ON DELETE SET NULL;
+
+CREATE TYPE reputation_reason AS ENUM (
+    'upvote', 'downvote', 'vote_undone', 'accepted', 'accept_undone'
+);
+
+-- Append-only: undoing a vote or accept adds an entry that cancels the
+-- original, and a user's reputation is the sum of their entries.
+CREATE TABLE IF NOT EXISTS reputation_events (
+    id uuid PRIMARY KEY,
+    user_id uuid NOT NULL REFERENCES users ON DELETE CASCADE,
+    delta INTEGER NOT NULL,
+    reason reputation_reason NOT NULL,
+    actor_id uuid,
+    question_id uuid REFERENCES questions ON DELETE SET NULL,
+    answer_id uuid REFERENCES answers ON DELETE SET NULL,
+    created_on TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX reputation_events_user_id_idx ON reputation_events (user_id, created_on DESC);
+
+CREATE TYPE badge_kind AS ENUM ('first_answer', 'ten_accepted');
+
+CREATE TABLE IF NOT EXISTS user_badges (
+    user_id uuid NOT NULL REFERENCES users ON DELETE CASCADE,
+    badge badge_kind NOT NULL,
+    awarded_on TIMESTAMP NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_id, badge)
+);
+
+INSERT INTO user_badges (user_id, badge, awarded_on)
+SELECT a.user_id, 'first_answer', MIN(a.created_on)
+FROM answers a
+JOIN users u ON u.id = a.user_id
+GROUP BY a.user_id;
@@ -0,0 +1,7 @@
+DROP TABLE IF EXISTS close_votes;
+ALTER TABLE questions
+DROP COLUMN IF EXISTS duplicate_of,
+DROP COLUMN IF EXISTS close_reason,
+DROP COLUMN IF EXISTS status;
+DROP TYPE IF EXISTS close_reason;
+DROP TYPE IF EXISTS question_status;
@@ -0,0 +1,20 @@
+CREATE TYPE question_status AS ENUM ('open', 'closed', 'locked');
+
+CREATE TYPE close_reason AS ENUM (
+    'duplicate', 'off_topic', 'unclear', 'too_broad', 'opinion_based'
+);
+
+ALTER TABLE questions
+ADD COLUMN status question_status NOT NULL DEFAULT 'open',
+ADD COLUMN close_reason close_reason,
+ADD COLUMN duplicate_of uuid REFERENCES questions ON DELETE SET NULL;
+
+-- Pending votes to close an open question; cleared whenever its status changes.
+CREATE TABLE IF NOT EXISTS close_votes (
+    question_id uuid NOT NULL REFERENCES questions ON DELETE CASCADE,
+    user_id uuid NOT NULL REFERENCES users ON DELETE CASCADE,
+    reason close_reason NOT NULL,
+    duplicate_of uuid REFERENCES questions ON DELETE CASCADE,
+    created_on TIMESTAMP NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (question_id, user_id)
+);
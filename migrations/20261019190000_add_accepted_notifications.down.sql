@@ -0,0 +1,10 @@
+ALTER TABLE notification_preferences DROP COLUMN IF EXISTS accepted;
+
+DELETE FROM notifications WHERE kind = 'accepted';
+
+-- Enum values can't be dropped, so the type is rebuilt without it.
+ALTER TYPE notification_kind RENAME TO notification_kind_old;
+CREATE TYPE notification_kind AS ENUM ('answer');
+ALTER TABLE notifications
+ALTER COLUMN kind TYPE notification_kind USING kind::text::notification_kind;
+DROP TYPE notification_kind_old;
@@ -0,0 +1,4 @@
+ALTER TYPE notification_kind ADD VALUE IF NOT EXISTS 'accepted';
+
+ALTER TABLE notification_preferences
+ADD COLUMN accepted BOOLEAN NOT NULL DEFAULT TRUE;
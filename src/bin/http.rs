@@ -1,41 +0,0 @@
-use std::env;
-
-use tracing::info;
-use tracing_subscriber::fmt::format::FmtSpan;
-
-#[tokio::main]
-async fn main() {
-    dotenv::dotenv().ok();
-
-    if let Err(_) = env::var("JWT_SECRET") {
-        panic!("JWT_SECRET is not set");
-    }
-
-    if let Err(_) = env::var("DATABASE_URL") {
-        panic!("DATABASE_URL is not set");
-    }
-
-    if let Err(_) = env::var("PORT") {
-        panic!("PORT is not set");
-    }
-
-    let log_filter =
-        std::env::var("RUST_LOG").unwrap_or_else(|_| "http=info,warp=error".to_owned());
-
-    tracing_subscriber::fmt()
-        .with_env_filter(log_filter)
-        .with_span_events(FmtSpan::CLOSE)
-        .init();
-
-    let db_url = env::var("DATABASE_URL").unwrap();
-    let store = askly::store::DbStore::new(&db_url).await;
-
-    sqlx::migrate!().run(&store.clone().conn).await.unwrap();
-
-    let port = env::var("PORT").unwrap().parse::<u16>().unwrap_or(3000);
-
-    let app = askly::build_routes(store).await;
-
-    info!("Listening on port:{}", port);
-    warp::serve(app).run(([0, 0, 0, 0], port)).await;
-}
@@ -0,0 +1,212 @@
+use askly::{
+    config::StorageConfig,
+    domain::user::{Role, User},
+    storage,
+    store::{hash_password, DbStore},
+};
+use clap::Subcommand;
+use rand::{distributions::Alphanumeric, Rng};
+use uuid::Uuid;
+
+#[derive(Debug, Subcommand)]
+pub enum UserCommand {
+    /// Create a user.
+    Create {
+        #[arg(long)]
+        name: String,
+        #[arg(long)]
+        email: String,
+        /// Generated and printed when omitted.
+        #[arg(long)]
+        password: Option<String>,
+        #[arg(long, default_value = "user")]
+        role: Role,
+    },
+    /// Change a user's role (user, moderator or admin).
+    Role { email: String, role: Role },
+    /// Set a new password; generated and printed when omitted.
+    ResetPassword {
+        email: String,
+        #[arg(long)]
+        password: Option<String>,
+    },
+    /// Prevent a user from signing in.
+    Disable { email: String },
+    /// Allow a disabled user to sign in again.
+    Enable { email: String },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ContentCommand {
+    /// Delete every question and answer posted by a user, with their attachments.
+    Delete { email: String },
+    /// Move every question and answer of one user to another.
+    Reassign {
+        #[arg(long)]
+        from: String,
+        #[arg(long)]
+        to: String,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum QuestionsCommand {
+    /// List the most recently asked questions.
+    Recent {
+        #[arg(long, default_value_t = 20)]
+        limit: i64,
+    },
+}
+
+pub async fn run_user(store: &DbStore, command: UserCommand) -> Result<(), String> {
+    match command {
+        UserCommand::Create {
+            name,
+            email,
+            password,
+            role,
+        } => {
+            let (password, generated) = password_or_generated(password);
+
+            let user = User {
+                id: Uuid::new_v4(),
+                name,
+                email,
+                password: hash_password(password.as_bytes()),
+                role,
+                disabled: false,
+            };
+
+            store
+                .add_user(user.clone())
+                .await
+                .map_err(|e| e.to_string())?;
+            println!("Created user {} ({})", user.email, user.id);
+            if generated {
+                println!("Password: {}", password);
+            }
+        }
+
+        UserCommand::Role { email, role } => {
+            let user = find_user(store, &email).await?;
+            store
+                .set_user_role(user.id, role)
+                .await
+                .map_err(|e| e.to_string())?;
+            println!("{} is now {:?}", email, role);
+        }
+
+        UserCommand::ResetPassword { email, password } => {
+            let user = find_user(store, &email).await?;
+            let (password, generated) = password_or_generated(password);
+            store
+                .set_user_password(user.id, &hash_password(password.as_bytes()))
+                .await
+                .map_err(|e| e.to_string())?;
+            println!("Password reset for {}", email);
+            if generated {
+                println!("Password: {}", password);
+            }
+        }
+
+        UserCommand::Disable { email } => {
+            let user = find_user(store, &email).await?;
+            store
+                .set_user_disabled(user.id, true)
+                .await
+                .map_err(|e| e.to_string())?;
+            println!("Disabled {}", email);
+        }
+
+        UserCommand::Enable { email } => {
+            let user = find_user(store, &email).await?;
+            store
+                .set_user_disabled(user.id, false)
+                .await
+                .map_err(|e| e.to_string())?;
+            println!("Enabled {}", email);
+        }
+    }
+
+    Ok(())
+}
+
+pub async fn run_content(store: &DbStore, command: ContentCommand) -> Result<(), String> {
+    match command {
+        ContentCommand::Delete { email } => {
+            let user = find_user(store, &email).await?;
+            let storage = StorageConfig::load()
+                .map(|config| storage::from_config(&config))
+                .map_err(|e| format!("Invalid configuration: {}", e))?;
+
+            let (deleted, attachments) = store
+                .delete_user_content(user.id)
+                .await
+                .map_err(|e| e.to_string())?;
+            let files = attachments.len();
+            storage::delete_bodies(storage.as_ref(), attachments).await;
+            println!(
+                "Deleted {} questions and answers and {} attachments of {}",
+                deleted, files, email
+            );
+        }
+
+        ContentCommand::Reassign { from, to } => {
+            let from_user = find_user(store, &from).await?;
+            let to_user = find_user(store, &to).await?;
+            let moved = store
+                .reassign_user_content(from_user.id, to_user.id)
+                .await
+                .map_err(|e| e.to_string())?;
+            println!(
+                "Moved {} questions and answers from {} to {}",
+                moved, from, to
+            );
+        }
+    }
+
+    Ok(())
+}
+
+pub async fn run_questions(store: &DbStore, command: QuestionsCommand) -> Result<(), String> {
+    match command {
+        QuestionsCommand::Recent { limit } => {
+            let questions = store
+                .recent_questions(limit)
+                .await
+                .map_err(|e| e.to_string())?;
+
+            for q in questions {
+                println!(
+                    "{}  {}  {}",
+                    q.created_on.format("%Y-%m-%d %H:%M"),
+                    q.id,
+                    q.title
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn find_user(store: &DbStore, email: &str) -> Result<User, String> {
+    store
+        .find_user_by_email(email)
+        .await
+        .map_err(|e| format!("{}: {}", email, e))
+}
+
+fn password_or_generated(password: Option<String>) -> (String, bool) {
+    match password {
+        Some(password) => (password, false),
+        None => {
+            let password = rand::thread_rng()
+                .sample_iter(&Alphanumeric)
+                .take(20)
+                .map(char::from)
+                .collect();
+            (password, true)
+        }
+    }
+}
@@ -0,0 +1,228 @@
+mod admin;
+mod seed;
+mod webhooks;
+
+use askly::{
+    config::{Config, DatabaseConfig},
+    events::EventBus,
+    migrate::{self, MigrationState},
+    shutdown::Shutdown,
+    store::DbStore,
+};
+use clap::{Parser, Subcommand};
+use tokio::sync::oneshot;
+use tracing::{info, warn};
+
+/// Askly server and admin tooling.
+#[derive(Debug, Parser)]
+#[command(name = env!("CARGO_BIN_NAME"), version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Run the HTTP server (the default).
+    Serve {
+        /// Do not apply pending migrations on startup.
+        #[arg(long)]
+        no_migrate: bool,
+    },
+    /// Manage database migrations.
+    Migrate {
+        #[command(subcommand)]
+        command: MigrateCommand,
+    },
+    /// Manage user accounts.
+    User {
+        #[command(subcommand)]
+        command: admin::UserCommand,
+    },
+    /// Delete or reassign a user's questions and answers.
+    Content {
+        #[command(subcommand)]
+        command: admin::ContentCommand,
+    },
+    /// Inspect questions.
+    Questions {
+        #[command(subcommand)]
+        command: admin::QuestionsCommand,
+    },
+    /// Fill the database with generated users, questions and answers.
+    Seed(seed::SeedArgs),
+    /// Webhook tooling.
+    Webhooks {
+        #[command(subcommand)]
+        command: webhooks::WebhooksCommand,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum MigrateCommand {
+    /// Apply all pending migrations.
+    Up,
+    /// Revert the latest migration, or every migration newer than `--to`.
+    Down {
+        #[arg(long)]
+        to: Option<i64>,
+    },
+    /// List migrations and whether they are applied.
+    Status,
+}
+
+#[tokio::main]
+async fn main() {
+    let cli = Cli::parse();
+    let command = cli.command.unwrap_or(Command::Serve { no_migrate: false });
+
+    if let Err(e) = run(command).await {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
+
+/// Each command loads only the configuration it needs: the server the full
+/// [`Config`], database commands just the connection settings, and the
+/// webhook receiver nothing at all.
+async fn run(command: Command) -> Result<(), String> {
+    if let Command::Serve { no_migrate } = command {
+        return serve(no_migrate).await;
+    }
+
+    // One-off commands print their own output; per-query spans would drown it.
+    askly::telemetry::init_cli("warn");
+
+    match command {
+        Command::Serve { .. } => unreachable!("handled above"),
+        Command::Webhooks { command } => webhooks::run(command).await,
+        Command::Migrate { command } => run_migrate(&connect().await?, command).await,
+        Command::User { command } => admin::run_user(&connect().await?, command).await,
+        Command::Content { command } => admin::run_content(&connect().await?, command).await,
+        Command::Questions { command } => admin::run_questions(&connect().await?, command).await,
+        Command::Seed(args) => seed::run(&connect().await?, args).await,
+    }
+}
+
+async fn connect() -> Result<DbStore, String> {
+    let config = DatabaseConfig::load().map_err(|e| format!("Invalid configuration: {}", e))?;
+    Ok(DbStore::new(&config.database_url, config.db_pool_size).await)
+}
+
+async fn run_migrate(store: &DbStore, command: MigrateCommand) -> Result<(), String> {
+    match command {
+        MigrateCommand::Up => {
+            migrate::up(store).await.map_err(|e| e.to_string())?;
+            println!("Migrations applied");
+        }
+
+        MigrateCommand::Down { to } => match migrate::down(store, to).await {
+            Ok(Some(version)) => println!("Reverted to version {}", version),
+            Ok(None) => println!("No applied migrations to revert"),
+            Err(e) => return Err(e.to_string()),
+        },
+
+        MigrateCommand::Status => {
+            for m in migrate::status(store).await.map_err(|e| e.to_string())? {
+                let state = match m.state {
+                    MigrationState::Applied => "applied",
+                    MigrationState::Pending => "pending",
+                    MigrationState::Modified => "modified",
+                };
+                println!("{:<16} {:<9} {}", m.version, state, m.description);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn serve(no_migrate: bool) -> Result<(), String> {
+    let config = Config::load().map_err(|e| format!("Invalid configuration: {}", e))?;
+    let _telemetry = askly::telemetry::init(&config, "http=info,askly=info,warp=error");
+    let store = DbStore::new(&config.database_url, config.db_pool_size).await;
+
+    if config.auto_migrate && !no_migrate {
+        migrate::up(&store)
+            .await
+            .map_err(|e| format!("Migration failed: {}", e))?;
+    }
+
+    let shutdown_timeout = config.shutdown_timeout;
+    let (stop_tasks, tasks_shutdown) = Shutdown::new();
+    let (events, listener) = EventBus::listen(store.clone(), tasks_shutdown.clone());
+    let worker = askly::webhooks::spawn(store.clone(), tasks_shutdown);
+    let app = askly::build_routes(store.clone(), config.clone(), events).await;
+
+    let (stop, stopped) = oneshot::channel::<()>();
+    let (addr, server) = warp::serve(app)
+        .try_bind_with_graceful_shutdown(config.bind_addr, async {
+            stopped.await.ok();
+        })
+        .map_err(|e| format!("Failed to bind {}: {}", config.bind_addr, e))?;
+
+    info!("Listening on {}", addr);
+    let server = tokio::spawn(server);
+
+    shutdown_signal().await;
+    info!("Shutting down, draining in-flight requests");
+    stop.send(()).ok();
+
+    if tokio::time::timeout(shutdown_timeout, server)
+        .await
+        .is_err()
+    {
+        warn!(
+            "In-flight requests still running after {:?}, exiting",
+            shutdown_timeout
+        );
+    }
+
+    // Background tasks outlive the requests that may still enqueue work for
+    // them, and stop before the pool closes under them.
+    stop_tasks.fire();
+    if tokio::time::timeout(shutdown_timeout, async {
+        listener.await.ok();
+        worker.await.ok();
+    })
+    .await
+    .is_err()
+    {
+        warn!(
+            "Background tasks still running after {:?}, exiting",
+            shutdown_timeout
+        );
+    }
+
+    store.conn.close().await;
+    info!("Shutdown complete");
+
+    Ok(())
+}
+
+/// Resolves on Ctrl+C, or SIGTERM on unix.
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c().await.ok();
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                sigterm.recv().await;
+            }
+            Err(_) => std::future::pending().await,
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
@@ -0,0 +1,245 @@
+use askly::{
+    config::ReputationConfig,
+    domain::{
+        answer::Answer,
+        question::{Question, QuestionStatus},
+        reputation::VoteTarget,
+        user::{Role, User},
+    },
+    error::Error,
+    markdown,
+    store::{hash_password, DbStore},
+};
+use clap::Args;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use uuid::{Builder, Uuid};
+
+/// Every seeded user signs in with this password.
+const PASSWORD: &str = "askly";
+
+const WORDS: &[&str] = &[
+    "async",
+    "borrow",
+    "cargo",
+    "closure",
+    "crate",
+    "database",
+    "deadlock",
+    "enum",
+    "error",
+    "future",
+    "generic",
+    "index",
+    "iterator",
+    "lifetime",
+    "macro",
+    "migration",
+    "module",
+    "mutex",
+    "pool",
+    "query",
+    "runtime",
+    "schema",
+    "serde",
+    "slice",
+    "stream",
+    "string",
+    "struct",
+    "tokio",
+    "trait",
+    "transaction",
+    "vector",
+    "warp",
+];
+
+const TAGS: &[&str] = &[
+    "rust",
+    "sql",
+    "postgres",
+    "async",
+    "tokio",
+    "warp",
+    "sqlx",
+    "serde",
+    "performance",
+    "testing",
+    "deployment",
+    "security",
+];
+
+#[derive(Debug, Args)]
+pub struct SeedArgs {
+    /// Seed for the pseudo-random generator; the same seed yields the same data.
+    #[arg(long, default_value_t = 42)]
+    seed: u64,
+    #[arg(long, default_value_t = 20)]
+    users: usize,
+    #[arg(long, default_value_t = 200)]
+    questions: usize,
+    /// Upper bound of answers per question.
+    #[arg(long, default_value_t = 5)]
+    max_answers: usize,
+    /// Upper bound of votes per question and per answer.
+    #[arg(long, default_value_t = 5)]
+    max_votes: usize,
+}
+
+/// Fills the database with users, tagged questions, answers and votes. The
+/// API has no comments, so there are none to seed.
+pub async fn run(store: &DbStore, args: SeedArgs) -> Result<(), String> {
+    if args.users == 0 {
+        return Err("--users must be at least 1".to_string());
+    }
+
+    // Votes earn reputation as configured, like votes cast through the API.
+    let points = ReputationConfig::load().map_err(|e| format!("Invalid configuration: {}", e))?;
+
+    let mut rng = StdRng::seed_from_u64(args.seed);
+
+    // Hashing is deliberately slow, so every seeded user shares one hash.
+    let password = hash_password(PASSWORD.as_bytes());
+
+    let mut users = Vec::with_capacity(args.users);
+    for n in 0..args.users {
+        let user = User {
+            id: uuid(&mut rng),
+            name: format!("Seed User {}", n + 1),
+            email: format!("seed-{}-{}@example.com", args.seed, n + 1),
+            password: password.clone(),
+            role: Role::User,
+            disabled: false,
+        };
+
+        match store.add_user(user.clone()).await {
+            Ok(_) => users.push(user.id),
+            Err(Error::DbError(sqlx::Error::Database(e))) if e.is_unique_violation() => {
+                return Err(format!(
+                    "{} already exists; seed {} has been loaded, pick another --seed",
+                    user.email, args.seed
+                ));
+            }
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+
+    let mut answers = 0;
+    let mut votes = 0;
+    for _ in 0..args.questions {
+        let tag_count = rng.gen_range(1..=3);
+        let id = uuid(&mut rng);
+        let title = format!("How do I {}?", sentence(&mut rng, 4, 9));
+        let content = paragraph(&mut rng);
+        let question = Question {
+            id,
+            title,
+            content_html: markdown::render(&content),
+            content,
+            tags: Some(
+                TAGS.choose_multiple(&mut rng, tag_count)
+                    .map(|tag| tag.to_string())
+                    .collect(),
+            ),
+            version: 1,
+            status: QuestionStatus::Open,
+            close_reason: None,
+            duplicate_of: None,
+            bookmarked: None,
+        };
+
+        let author = *users.choose(&mut rng).unwrap();
+        store
+            .add_question(&question, author)
+            .await
+            .map_err(|e| e.to_string())?;
+        let target = VoteTarget::Question(question.id);
+        votes += vote(store, &mut rng, &args, &users, author, target, &points).await?;
+
+        for _ in 0..rng.gen_range(0..=args.max_answers) {
+            let id = uuid(&mut rng);
+            let content = paragraph(&mut rng);
+            let answer = Answer {
+                id,
+                content_html: markdown::render(&content),
+                content,
+                question_id: question.id,
+                version: 1,
+            };
+
+            let author = *users.choose(&mut rng).unwrap();
+            store
+                .add_answer(answer, author)
+                .await
+                .map_err(|e| e.to_string())?;
+            answers += 1;
+            let target = VoteTarget::Answer(id);
+            votes += vote(store, &mut rng, &args, &users, author, target, &points).await?;
+        }
+    }
+
+    println!(
+        "Seeded {} users, {} questions, {} answers and {} votes (password `{}`)",
+        users.len(),
+        args.questions,
+        answers,
+        votes,
+        PASSWORD
+    );
+
+    Ok(())
+}
+
+/// Casts up to `--max-votes` votes on the post from distinct users other than
+/// its author, mostly upvotes. Returns how many were cast.
+async fn vote(
+    store: &DbStore,
+    rng: &mut StdRng,
+    args: &SeedArgs,
+    users: &[Uuid],
+    author: Uuid,
+    target: VoteTarget,
+    points: &ReputationConfig,
+) -> Result<usize, String> {
+    let count = rng.gen_range(0..=args.max_votes);
+    let voters: Vec<Uuid> = users
+        .iter()
+        .copied()
+        .filter(|&user| user != author)
+        .collect::<Vec<_>>()
+        .choose_multiple(rng, count)
+        .copied()
+        .collect();
+
+    for &voter in &voters {
+        let value = if rng.gen_bool(0.8) { 1 } else { -1 };
+        store
+            .vote(voter, target, value, points)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+
+    Ok(voters.len())
+}
+
+fn uuid(rng: &mut StdRng) -> Uuid {
+    Builder::from_random_bytes(rng.gen()).into_uuid()
+}
+
+fn sentence(rng: &mut StdRng, min: usize, max: usize) -> String {
+    let len = rng.gen_range(min..=max);
+    (0..len)
+        .map(|_| *WORDS.choose(rng).unwrap())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn paragraph(rng: &mut StdRng) -> String {
+    let len = rng.gen_range(2..=6);
+    (0..len)
+        .map(|_| {
+            let mut s = sentence(rng, 6, 16);
+            s[..1].make_ascii_uppercase();
+            s + "."
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
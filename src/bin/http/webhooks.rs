@@ -0,0 +1,51 @@
+use std::net::SocketAddr;
+
+use askly::webhooks;
+use clap::Subcommand;
+use tokio::sync::mpsc;
+
+#[derive(Debug, Subcommand)]
+pub enum WebhooksCommand {
+    /// Run a local receiver that checks signatures and prints every delivery.
+    Receive {
+        #[arg(long, default_value = "127.0.0.1:4000")]
+        bind: SocketAddr,
+        /// Secret the webhook was created with.
+        #[arg(long)]
+        secret: String,
+        /// Answer the first N deliveries with 500 to exercise retries.
+        #[arg(long, default_value_t = 0)]
+        fail: usize,
+    },
+}
+
+pub async fn run(command: WebhooksCommand) -> Result<(), String> {
+    match command {
+        WebhooksCommand::Receive { bind, secret, fail } => receive(bind, secret, fail).await,
+    }
+}
+
+async fn receive(bind: SocketAddr, secret: String, fail: usize) -> Result<(), String> {
+    let (sink, mut received) = mpsc::unbounded_channel();
+
+    let (addr, server) = warp::serve(webhooks::receiver(secret, fail, sink))
+        .try_bind_ephemeral(bind)
+        .map_err(|e| format!("Failed to bind {}: {}", bind, e))?;
+
+    println!("Receiving webhooks on http://{}", addr);
+    tokio::spawn(server);
+
+    while let Some(delivery) = received.recv().await {
+        println!(
+            "#{} {} {} signature={} -> {}",
+            delivery.number,
+            delivery.event,
+            delivery.delivery,
+            if delivery.verified { "ok" } else { "BAD" },
+            delivery.status.as_u16()
+        );
+        println!("{}", delivery.body);
+    }
+
+    Ok(())
+}
@@ -1,6 +1,5 @@
-use std::env;
-
-use shuttle_runtime::SecretStore;
+use askly::{config::Config, events::EventBus, shutdown::Shutdown};
+use shuttle_runtime::{CustomError, SecretStore};
 use warp::Reply;
 
 #[shuttle_runtime::main]
@@ -8,11 +7,21 @@ async fn warp(
     #[shuttle_shared_db::Postgres] conn_str: String,
     #[shuttle_runtime::Secrets] secrets: SecretStore,
 ) -> shuttle_warp::ShuttleWarp<(impl Reply,)> {
-    env::set_var("JWT_SECRET", secrets.get("JWT_SECRET").unwrap());
+    let config = Config::from_source(|key| match key {
+        "DATABASE_URL" => Some(conn_str.clone()),
+        key => secrets.get(key),
+    })
+    .map_err(CustomError::new)?;
+
+    let store = askly::store::DbStore::new(&config.database_url, config.db_pool_size).await;
+    if config.auto_migrate {
+        askly::migrate::up(&store).await.map_err(CustomError::new)?;
+    }
 
-    let store = askly::store::DbStore::new(&conn_str).await;
-    sqlx::migrate!().run(&store.clone().conn).await.unwrap();
+    // Shuttle has no shutdown hook; the tasks run until the process exits.
+    let (events, _) = EventBus::listen(store.clone(), Shutdown::never());
+    askly::webhooks::spawn(store.clone(), Shutdown::never());
 
-    let app = askly::build_routes(store).await;
+    let app = askly::build_routes(store, config, events).await;
     Ok(app.into())
 }
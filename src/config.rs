@@ -0,0 +1,711 @@
+use std::{
+    env, fmt, fs,
+    net::{IpAddr, SocketAddr},
+    path::PathBuf,
+    time::Duration,
+};
+
+use warp::http::{header::HeaderName, Method, Uri};
+
+use crate::domain::reputation::Privilege;
+
+/// Application configuration, loaded once at startup.
+///
+/// Every key can be given in the TOML file named by `ASKLY_CONFIG` (as
+/// `snake_case`) or as an environment variable (as `UPPER_CASE`); the
+/// environment wins.
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub database_url: String,
+    pub db_pool_size: u32,
+    /// Apply pending migrations when the server starts.
+    pub auto_migrate: bool,
+    pub bind_addr: SocketAddr,
+    /// How long in-flight requests may take to finish after a shutdown signal.
+    pub shutdown_timeout: Duration,
+    pub jwt_secret: String,
+    pub token_ttl_hours: i64,
+    /// Origins allowed to call the API from a browser; `*` allows any.
+    /// CORS is disabled when empty.
+    pub cors_origins: Vec<String>,
+    pub cors_methods: Vec<Method>,
+    pub cors_headers: Vec<HeaderName>,
+    pub cors_allow_credentials: bool,
+    pub cors_max_age_secs: u64,
+    /// Requests per minute per client address; `None` disables limiting.
+    pub rate_limit_per_minute: Option<u32>,
+    pub log_format: LogFormat,
+    /// OTLP/HTTP traces endpoint, e.g. `http://localhost:4318/v1/traces`;
+    /// trace export is disabled when `None`.
+    pub otlp_endpoint: Option<String>,
+    pub service_name: String,
+    pub storage: StorageConfig,
+    /// Largest accepted attachment, in bytes.
+    pub attachment_max_bytes: u64,
+    /// Accepted attachment media types.
+    pub attachment_types: Vec<String>,
+    pub reputation: ReputationConfig,
+    pub privileges: PrivilegeConfig,
+    /// Votes from users with [`Privilege::VoteToClose`] that close a question.
+    pub close_votes: i64,
+}
+
+/// The part of [`Config`] that commands working only on the database need,
+/// so they run without the server's settings such as `JWT_SECRET`.
+#[derive(Debug, Clone)]
+pub struct DatabaseConfig {
+    pub database_url: String,
+    pub db_pool_size: u32,
+}
+
+impl DatabaseConfig {
+    /// `DATABASE_URL` and `DB_POOL_SIZE`, from the sources [`Config::load`] reads.
+    pub fn load() -> Result<Self, ConfigError> {
+        dotenv::dotenv().ok();
+        Self::from_source(|key| env::var(key).ok())
+    }
+
+    pub fn from_source(lookup: impl Fn(&str) -> Option<String>) -> Result<Self, ConfigError> {
+        Self::parse(&Source::new(lookup)?)
+    }
+
+    fn parse<F: Fn(&str) -> Option<String>>(source: &Source<F>) -> Result<Self, ConfigError> {
+        let database_url = source.required("database_url")?;
+        if !database_url.starts_with("postgres://") && !database_url.starts_with("postgresql://") {
+            return Err(ConfigError::Invalid {
+                key: "database_url",
+                value: database_url,
+                expected: "a postgres:// connection string",
+            });
+        }
+
+        let db_pool_size = source.parse("db_pool_size", 5, "a positive number")?;
+        if db_pool_size == 0 {
+            return Err(ConfigError::Invalid {
+                key: "db_pool_size",
+                value: db_pool_size.to_string(),
+                expected: "a positive number",
+            });
+        }
+
+        Ok(DatabaseConfig {
+            database_url,
+            db_pool_size,
+        })
+    }
+}
+
+/// Reputation points per event. Votes only ever move the author's
+/// reputation; an accepted answer also rewards its author.
+#[derive(Debug, Clone, Copy)]
+pub struct ReputationConfig {
+    pub question_upvote: i32,
+    pub answer_upvote: i32,
+    /// Points a downvote takes from the author.
+    pub downvote: i32,
+    pub accepted: i32,
+    /// Most points a user can gain from upvotes per day.
+    pub daily_cap: i32,
+}
+
+impl ReputationConfig {
+    /// The `REPUTATION_*` keys, for commands that award points outside the server.
+    pub fn load() -> Result<Self, ConfigError> {
+        dotenv::dotenv().ok();
+        Self::from_source(|key| env::var(key).ok())
+    }
+
+    pub fn from_source(lookup: impl Fn(&str) -> Option<String>) -> Result<Self, ConfigError> {
+        Self::parse(&Source::new(lookup)?)
+    }
+
+    fn parse<F: Fn(&str) -> Option<String>>(source: &Source<F>) -> Result<Self, ConfigError> {
+        let points = |key, default| source.parse_at_least(key, default, 0, "a number, 0 or more");
+
+        Ok(ReputationConfig {
+            question_upvote: points("reputation_question_upvote", 5)?,
+            answer_upvote: points("reputation_answer_upvote", 10)?,
+            downvote: points("reputation_downvote", 2)?,
+            accepted: points("reputation_accepted", 15)?,
+            daily_cap: points("reputation_daily_cap", 200)?,
+        })
+    }
+}
+
+/// Reputation needed for each privilege. Admins have every privilege.
+#[derive(Debug, Clone, Copy)]
+pub struct PrivilegeConfig {
+    pub downvote: i64,
+    /// Editing questions posted by someone else.
+    pub edit_others: i64,
+    /// Tagging a question with a tag no other question uses yet.
+    pub create_tags: i64,
+    pub vote_to_close: i64,
+}
+
+impl PrivilegeConfig {
+    pub fn required(&self, privilege: Privilege) -> i64 {
+        match privilege {
+            Privilege::Downvote => self.downvote,
+            Privilege::EditOthers => self.edit_others,
+            Privilege::CreateTags => self.create_tags,
+            Privilege::VoteToClose => self.vote_to_close,
+        }
+    }
+
+    fn parse<F: Fn(&str) -> Option<String>>(source: &Source<F>) -> Result<Self, ConfigError> {
+        let reputation =
+            |key, default| source.parse_at_least(key, default, 0, "a reputation, 0 or more");
+
+        Ok(PrivilegeConfig {
+            downvote: reputation("privilege_downvote", 125)?,
+            edit_others: reputation("privilege_edit_others", 2000)?,
+            create_tags: reputation("privilege_create_tags", 1500)?,
+            vote_to_close: reputation("privilege_vote_to_close", 3000)?,
+        })
+    }
+}
+
+/// Where attachment bodies are kept.
+#[derive(Debug, Clone)]
+pub enum StorageConfig {
+    Local {
+        path: PathBuf,
+    },
+    /// Any S3-compatible service, addressed path-style
+    /// (`{endpoint}/{bucket}/{key}`).
+    S3 {
+        endpoint: String,
+        bucket: String,
+        region: String,
+        access_key: String,
+        secret_key: String,
+    },
+}
+
+impl StorageConfig {
+    /// `STORAGE_BACKEND` and the keys of the backend it names.
+    pub fn load() -> Result<Self, ConfigError> {
+        dotenv::dotenv().ok();
+        Self::from_source(|key| env::var(key).ok())
+    }
+
+    pub fn from_source(lookup: impl Fn(&str) -> Option<String>) -> Result<Self, ConfigError> {
+        Self::parse(&Source::new(lookup)?)
+    }
+
+    fn parse<F: Fn(&str) -> Option<String>>(source: &Source<F>) -> Result<Self, ConfigError> {
+        Ok(match source.get("storage_backend").as_deref() {
+            None | Some("local") => StorageConfig::Local {
+                path: source
+                    .get("storage_path")
+                    .unwrap_or_else(|| "uploads".to_string())
+                    .into(),
+            },
+            Some("s3") => {
+                let endpoint = source.required("s3_endpoint")?;
+                if !is_http_url(&endpoint) {
+                    return Err(ConfigError::Invalid {
+                        key: "s3_endpoint",
+                        value: endpoint,
+                        expected: "an http:// or https:// url",
+                    });
+                }
+
+                StorageConfig::S3 {
+                    endpoint: endpoint.trim_end_matches('/').to_string(),
+                    bucket: source.required("s3_bucket")?,
+                    region: source
+                        .get("s3_region")
+                        .unwrap_or_else(|| "us-east-1".to_string()),
+                    access_key: source.required("s3_access_key")?,
+                    secret_key: source.required("s3_secret_key")?,
+                }
+            }
+            Some(other) => {
+                return Err(ConfigError::Invalid {
+                    key: "storage_backend",
+                    value: other.to_string(),
+                    expected: "`local` or `s3`",
+                })
+            }
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    Text,
+    Json,
+}
+
+impl std::str::FromStr for LogFormat {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Missing(&'static str),
+    Invalid {
+        key: &'static str,
+        value: String,
+        expected: &'static str,
+    },
+    File(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Missing(key) => write!(f, "{} is not set", key.to_uppercase()),
+            ConfigError::Invalid {
+                key,
+                value,
+                expected,
+            } => write!(
+                f,
+                "invalid {} `{}`: expected {}",
+                key.to_uppercase(),
+                value,
+                expected
+            ),
+            ConfigError::File(e) => write!(f, "config file: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl Config {
+    /// Loads `.env`, the optional `ASKLY_CONFIG` file and the process environment.
+    pub fn load() -> Result<Self, ConfigError> {
+        dotenv::dotenv().ok();
+        Self::from_source(|key| env::var(key).ok())
+    }
+
+    /// Builds the config from a key lookup (e.g. a secret store) layered over
+    /// the optional `ASKLY_CONFIG` file.
+    pub fn from_source(lookup: impl Fn(&str) -> Option<String>) -> Result<Self, ConfigError> {
+        let source = Source::new(lookup)?;
+
+        let DatabaseConfig {
+            database_url,
+            db_pool_size,
+        } = DatabaseConfig::parse(&source)?;
+        // The event listener keeps one connection of the pool for itself.
+        if db_pool_size < 2 {
+            return Err(ConfigError::Invalid {
+                key: "db_pool_size",
+                value: db_pool_size.to_string(),
+                expected: "at least 2, one being held by the event listener",
+            });
+        }
+
+        let jwt_secret = source.required("jwt_secret")?;
+
+        let auto_migrate = source.parse("auto_migrate", true, "true or false")?;
+
+        let host: IpAddr = source.parse("host", [0, 0, 0, 0].into(), "an IP address")?;
+        let port: u16 = source.parse("port", 3000, "a port number")?;
+
+        let shutdown_timeout_secs =
+            source.parse("shutdown_timeout_secs", 30, "a number of seconds")?;
+
+        let token_ttl_hours = source.parse("token_ttl_hours", 24 * 7, "a number of hours")?;
+        if token_ttl_hours <= 0 {
+            return Err(ConfigError::Invalid {
+                key: "token_ttl_hours",
+                value: token_ttl_hours.to_string(),
+                expected: "a positive number of hours",
+            });
+        }
+
+        let cors_origins = source.list("cors_origins");
+        if let Some(origin) = cors_origins.iter().find(|o| *o != "*" && !is_origin(o)) {
+            return Err(ConfigError::Invalid {
+                key: "cors_origins",
+                value: origin.clone(),
+                expected: "`*` or origins like https://example.com",
+            });
+        }
+
+        let cors_methods = source.parse_list(
+            "cors_methods",
+            &["GET", "POST", "PUT", "DELETE"],
+            "HTTP methods",
+        )?;
+
+        let cors_headers = source.parse_list(
+            "cors_headers",
+            &["authorization", "content-type", "if-match", "if-none-match"],
+            "header names",
+        )?;
+
+        let cors_allow_credentials =
+            source.parse("cors_allow_credentials", false, "true or false")?;
+        if cors_allow_credentials && cors_origins.iter().any(|o| o == "*") {
+            return Err(ConfigError::Invalid {
+                key: "cors_allow_credentials",
+                value: "true".to_string(),
+                expected: "explicit CORS_ORIGINS when credentials are allowed, not `*`",
+            });
+        }
+
+        let cors_max_age_secs = source.parse("cors_max_age_secs", 3600, "a number of seconds")?;
+
+        let rate_limit_per_minute = match source.parse("rate_limit_per_minute", 0, "a number")? {
+            0 => None,
+            n => Some(n),
+        };
+
+        let log_format = source.parse("log_format", LogFormat::Text, "`text` or `json`")?;
+
+        let otlp_endpoint = source.get("otlp_endpoint").filter(|e| !e.is_empty());
+        if let Some(endpoint) = otlp_endpoint.as_ref().filter(|e| !is_http_url(e)) {
+            return Err(ConfigError::Invalid {
+                key: "otlp_endpoint",
+                value: endpoint.clone(),
+                expected: "an http:// or https:// url",
+            });
+        }
+
+        let service_name = source
+            .get("service_name")
+            .unwrap_or_else(|| "askly".to_string());
+
+        let storage = StorageConfig::parse(&source)?;
+
+        let attachment_max_bytes = source.parse(
+            "attachment_max_bytes",
+            10 * 1024 * 1024,
+            "a number of bytes",
+        )?;
+
+        let mut attachment_types = source.list("attachment_types");
+        if attachment_types.is_empty() {
+            attachment_types = DEFAULT_ATTACHMENT_TYPES
+                .iter()
+                .map(|t| t.to_string())
+                .collect();
+        }
+
+        let reputation = ReputationConfig::parse(&source)?;
+
+        let privileges = PrivilegeConfig::parse(&source)?;
+
+        let close_votes = source.parse_at_least("close_votes", 3, 1, "a positive number")?;
+
+        Ok(Config {
+            database_url,
+            db_pool_size,
+            auto_migrate,
+            bind_addr: SocketAddr::new(host, port),
+            shutdown_timeout: Duration::from_secs(shutdown_timeout_secs),
+            jwt_secret,
+            token_ttl_hours,
+            cors_origins,
+            cors_methods,
+            cors_headers,
+            cors_allow_credentials,
+            cors_max_age_secs,
+            rate_limit_per_minute,
+            log_format,
+            otlp_endpoint,
+            service_name,
+            storage,
+            attachment_max_bytes,
+            attachment_types,
+            reputation,
+            privileges,
+            close_votes,
+        })
+    }
+}
+
+const DEFAULT_ATTACHMENT_TYPES: &[&str] = &[
+    "image/png",
+    "image/jpeg",
+    "image/gif",
+    "image/webp",
+    "text/plain",
+    "application/pdf",
+];
+
+fn is_origin(value: &str) -> bool {
+    match value.parse::<Uri>() {
+        Ok(uri) => {
+            matches!(uri.scheme_str(), Some("http" | "https"))
+                && uri.authority().is_some()
+                && uri.path() == "/"
+                && !value.ends_with('/')
+        }
+        Err(_) => false,
+    }
+}
+
+fn is_http_url(value: &str) -> bool {
+    match value.parse::<Uri>() {
+        Ok(uri) => matches!(uri.scheme_str(), Some("http" | "https")) && uri.authority().is_some(),
+        Err(_) => false,
+    }
+}
+
+fn read_file(path: &str) -> Result<toml::Table, ConfigError> {
+    let content =
+        fs::read_to_string(path).map_err(|e| ConfigError::File(format!("{}: {}", path, e)))?;
+
+    content
+        .parse::<toml::Table>()
+        .map_err(|e| ConfigError::File(format!("{}: {}", path, e)))
+}
+
+struct Source<F> {
+    lookup: F,
+    file: toml::Table,
+}
+
+impl<F: Fn(&str) -> Option<String>> Source<F> {
+    fn new(lookup: F) -> Result<Self, ConfigError> {
+        let file = match lookup("ASKLY_CONFIG") {
+            Some(path) => read_file(&path)?,
+            None => toml::Table::new(),
+        };
+
+        Ok(Source { lookup, file })
+    }
+
+    fn get(&self, key: &str) -> Option<String> {
+        if let Some(value) = (self.lookup)(&key.to_uppercase()) {
+            return Some(value);
+        }
+
+        match self.file.get(key)? {
+            toml::Value::String(s) => Some(s.clone()),
+            toml::Value::Array(items) => Some(
+                items
+                    .iter()
+                    .map(|v| v.as_str().map(str::to_string).unwrap_or(v.to_string()))
+                    .collect::<Vec<_>>()
+                    .join(","),
+            ),
+            value => Some(value.to_string()),
+        }
+    }
+
+    fn required(&self, key: &'static str) -> Result<String, ConfigError> {
+        match self.get(key) {
+            Some(value) if !value.is_empty() => Ok(value),
+            _ => Err(ConfigError::Missing(key)),
+        }
+    }
+
+    fn parse<T: std::str::FromStr>(
+        &self,
+        key: &'static str,
+        default: T,
+        expected: &'static str,
+    ) -> Result<T, ConfigError> {
+        match self.get(key) {
+            None => Ok(default),
+            Some(value) => value.trim().parse().map_err(|_| ConfigError::Invalid {
+                key,
+                value,
+                expected,
+            }),
+        }
+    }
+
+    /// Like [`Source::parse`], also rejecting values below `min`.
+    fn parse_at_least<T: std::str::FromStr + PartialOrd + ToString>(
+        &self,
+        key: &'static str,
+        default: T,
+        min: T,
+        expected: &'static str,
+    ) -> Result<T, ConfigError> {
+        match self.parse(key, default, expected)? {
+            value if value < min => Err(ConfigError::Invalid {
+                key,
+                value: value.to_string(),
+                expected,
+            }),
+            value => Ok(value),
+        }
+    }
+
+    fn parse_list<T: std::str::FromStr>(
+        &self,
+        key: &'static str,
+        default: &[&str],
+        expected: &'static str,
+    ) -> Result<Vec<T>, ConfigError> {
+        let mut values = self.list(key);
+        if values.is_empty() {
+            values = default.iter().map(|v| v.to_string()).collect();
+        }
+
+        values
+            .into_iter()
+            .map(|value| {
+                value.parse().map_err(|_| ConfigError::Invalid {
+                    key,
+                    value,
+                    expected,
+                })
+            })
+            .collect()
+    }
+
+    /// Comma separated in the environment, an array in the file.
+    fn list(&self, key: &str) -> Vec<String> {
+        self.get(key)
+            .map(|value| {
+                value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|s| !s.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn load(vars: &[(&str, &str)]) -> Result<Config, ConfigError> {
+        let mut env: HashMap<String, String> = [
+            ("DATABASE_URL", "postgres://localhost/askly"),
+            ("JWT_SECRET", "secret"),
+        ]
+        .iter()
+        .chain(vars)
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect();
+        env.retain(|_, value| !value.is_empty());
+
+        Config::from_source(|key| env.get(key).cloned())
+    }
+
+    fn error(vars: &[(&str, &str)]) -> String {
+        load(vars).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn loads_defaults() {
+        let config = load(&[]).unwrap();
+
+        assert_eq!(config.db_pool_size, 5);
+        assert_eq!(config.bind_addr, "0.0.0.0:3000".parse().unwrap());
+        assert_eq!(config.rate_limit_per_minute, None);
+        assert_eq!(config.close_votes, 3);
+        assert_eq!(config.privileges.downvote, 125);
+        assert_eq!(config.reputation.daily_cap, 200);
+    }
+
+    #[test]
+    fn reports_missing_keys() {
+        assert_eq!(error(&[("JWT_SECRET", "")]), "JWT_SECRET is not set");
+        assert_eq!(error(&[("DATABASE_URL", "")]), "DATABASE_URL is not set");
+    }
+
+    #[test]
+    fn reports_invalid_values() {
+        assert_eq!(
+            error(&[("DATABASE_URL", "mysql://localhost/askly")]),
+            "invalid DATABASE_URL `mysql://localhost/askly`: expected a postgres:// connection string"
+        );
+        assert_eq!(
+            error(&[("PORT", "http")]),
+            "invalid PORT `http`: expected a port number"
+        );
+        assert_eq!(
+            error(&[("CORS_ORIGINS", "example.com")]),
+            "invalid CORS_ORIGINS `example.com`: expected `*` or origins like https://example.com"
+        );
+        assert_eq!(
+            error(&[("RATE_LIMIT_PER_MINUTE", "-1")]),
+            "invalid RATE_LIMIT_PER_MINUTE `-1`: expected a number"
+        );
+    }
+
+    #[test]
+    fn the_server_needs_two_pool_connections() {
+        assert_eq!(
+            error(&[("DB_POOL_SIZE", "1")]),
+            "invalid DB_POOL_SIZE `1`: expected at least 2, one being held by the event listener"
+        );
+        assert_eq!(load(&[("DB_POOL_SIZE", "2")]).unwrap().db_pool_size, 2);
+
+        // Commands that only need the database don't listen for events.
+        let database = DatabaseConfig::from_source(|key| match key {
+            "DATABASE_URL" => Some("postgres://localhost/askly".to_string()),
+            "DB_POOL_SIZE" => Some("1".to_string()),
+            _ => None,
+        });
+        assert_eq!(database.unwrap().db_pool_size, 1);
+    }
+
+    #[test]
+    fn close_votes_must_be_positive() {
+        assert_eq!(
+            error(&[("CLOSE_VOTES", "0")]),
+            "invalid CLOSE_VOTES `0`: expected a positive number"
+        );
+        assert_eq!(
+            error(&[("CLOSE_VOTES", "-2")]),
+            "invalid CLOSE_VOTES `-2`: expected a positive number"
+        );
+        assert_eq!(load(&[("CLOSE_VOTES", "1")]).unwrap().close_votes, 1);
+    }
+
+    #[test]
+    fn reputation_points_must_not_be_negative() {
+        assert_eq!(
+            error(&[("REPUTATION_DOWNVOTE", "-2")]),
+            "invalid REPUTATION_DOWNVOTE `-2`: expected a number, 0 or more"
+        );
+        assert_eq!(
+            error(&[("REPUTATION_DAILY_CAP", "-1")]),
+            "invalid REPUTATION_DAILY_CAP `-1`: expected a number, 0 or more"
+        );
+        assert_eq!(
+            load(&[("REPUTATION_ACCEPTED", "0")])
+                .unwrap()
+                .reputation
+                .accepted,
+            0
+        );
+    }
+
+    #[test]
+    fn privilege_thresholds_must_not_be_negative() {
+        assert_eq!(
+            error(&[("PRIVILEGE_VOTE_TO_CLOSE", "-1")]),
+            "invalid PRIVILEGE_VOTE_TO_CLOSE `-1`: expected a reputation, 0 or more"
+        );
+        assert_eq!(
+            error(&[("PRIVILEGE_DOWNVOTE", "many")]),
+            "invalid PRIVILEGE_DOWNVOTE `many`: expected a reputation, 0 or more"
+        );
+        assert_eq!(
+            load(&[("PRIVILEGE_CREATE_TAGS", "0")])
+                .unwrap()
+                .privileges
+                .create_tags,
+            0
+        );
+    }
+}
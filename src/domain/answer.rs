@@ -1,14 +1,34 @@
 use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
 use uuid::Uuid;
 
-#[derive(Debug, Clone, Serialize)]
+use super::reputation::AuthorSummary;
+
+#[derive(Debug, Clone, Serialize, FromRow, ToSchema)]
 pub struct Answer {
     pub id: Uuid,
     pub content: String,
+    /// `content` rendered from Markdown and sanitized.
+    pub content_html: String,
     pub question_id: Uuid,
+    pub version: i32,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, ToSchema)]
 pub struct NewAnswer {
     pub content: String,
 }
+
+/// An answer as listed under its question.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct AnswerView {
+    #[serde(flatten)]
+    pub answer: Answer,
+    /// Sum of all votes on the answer.
+    pub score: i64,
+    /// Whether the question's author accepted this answer.
+    pub accepted: bool,
+    /// `None` when the author's account is gone.
+    pub author: Option<AuthorSummary>,
+}
@@ -0,0 +1,24 @@
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use sqlx::FromRow;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// A file attached to exactly one question or answer. The body is kept in
+/// `Storage` under the attachment's id.
+#[derive(Debug, Clone, Serialize, FromRow, ToSchema)]
+pub struct Attachment {
+    pub id: Uuid,
+    pub question_id: Option<Uuid>,
+    pub answer_id: Option<Uuid>,
+    pub filename: String,
+    pub content_type: String,
+    pub size: i64,
+    pub created_on: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum AttachmentTarget {
+    Question(Uuid),
+    Answer(Uuid),
+}
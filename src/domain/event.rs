@@ -0,0 +1,102 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use super::{answer::Answer, question::Question};
+
+/// A committed change as sent over Postgres `NOTIFY`. Only ids travel, which
+/// keeps payloads well under the 8000-byte limit whatever the content size.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Change {
+    AnswerAdded {
+        question_id: Uuid,
+        answer_id: Uuid,
+    },
+    AnswerDeleted {
+        question_id: Uuid,
+        answer_id: Uuid,
+    },
+    QuestionUpdated {
+        question_id: Uuid,
+    },
+    QuestionDeleted {
+        question_id: Uuid,
+    },
+    /// A vote on the question, or on one of its answers when `answer_id` is set.
+    Voted {
+        question_id: Uuid,
+        answer_id: Option<Uuid>,
+    },
+    AnswerAccepted {
+        question_id: Uuid,
+        answer_id: Uuid,
+    },
+    AnswerUnaccepted {
+        question_id: Uuid,
+        answer_id: Uuid,
+    },
+}
+
+/// A change to a question or its answers, as pushed to subscribers.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Event {
+    AnswerAdded {
+        question_id: Uuid,
+        answer: Answer,
+    },
+    AnswerDeleted {
+        question_id: Uuid,
+        answer_id: Uuid,
+    },
+    QuestionUpdated {
+        question_id: Uuid,
+        question: Question,
+    },
+    QuestionDeleted {
+        question_id: Uuid,
+    },
+    /// The score of the question, or of one of its answers when `answer_id`
+    /// is set, changed.
+    Voted {
+        question_id: Uuid,
+        answer_id: Option<Uuid>,
+        score: i64,
+    },
+    AnswerAccepted {
+        question_id: Uuid,
+        answer_id: Uuid,
+    },
+    AnswerUnaccepted {
+        question_id: Uuid,
+        answer_id: Uuid,
+    },
+}
+
+impl Event {
+    pub fn question_id(&self) -> Uuid {
+        match self {
+            Event::AnswerAdded { question_id, .. }
+            | Event::AnswerDeleted { question_id, .. }
+            | Event::QuestionUpdated { question_id, .. }
+            | Event::QuestionDeleted { question_id }
+            | Event::Voted { question_id, .. }
+            | Event::AnswerAccepted { question_id, .. }
+            | Event::AnswerUnaccepted { question_id, .. } => *question_id,
+        }
+    }
+
+    /// The `type` tag, also used as the SSE event name.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Event::AnswerAdded { .. } => "answer_added",
+            Event::AnswerDeleted { .. } => "answer_deleted",
+            Event::QuestionUpdated { .. } => "question_updated",
+            Event::QuestionDeleted { .. } => "question_deleted",
+            Event::Voted { .. } => "voted",
+            Event::AnswerAccepted { .. } => "answer_accepted",
+            Event::AnswerUnaccepted { .. } => "answer_unaccepted",
+        }
+    }
+}
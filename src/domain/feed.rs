@@ -0,0 +1,33 @@
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "text", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum FeedKind {
+    Question,
+    Answer,
+}
+
+/// A new question or answer from something the user follows.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct FeedItem {
+    pub kind: FeedKind,
+    /// Id of the question or answer.
+    pub id: Uuid,
+    pub question_id: Uuid,
+    pub question_title: String,
+    pub content_html: String,
+    pub author_id: Option<Uuid>,
+    pub created_on: NaiveDateTime,
+}
+
+/// Everything a user follows.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct Follows {
+    pub questions: Vec<Uuid>,
+    pub tags: Vec<String>,
+    pub users: Vec<Uuid>,
+}
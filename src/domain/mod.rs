@@ -1,3 +1,10 @@
 pub mod answer;
+pub mod attachment;
+pub mod event;
+pub mod feed;
+pub mod notification;
+pub mod page;
 pub mod question;
+pub mod reputation;
 pub mod user;
+pub mod webhook;
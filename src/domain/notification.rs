@@ -0,0 +1,65 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "notification_kind", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+    /// Someone answered one of the user's questions.
+    Answer,
+    /// One of the user's answers was accepted.
+    Accepted,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow, ToSchema)]
+pub struct Notification {
+    pub id: Uuid,
+    pub kind: NotificationKind,
+    pub question_id: Uuid,
+    pub answer_id: Option<Uuid>,
+    /// The user whose action caused the notification.
+    pub actor_id: Option<Uuid>,
+    pub read: bool,
+    pub created_on: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct NotificationList {
+    pub unread: i64,
+    pub notifications: Vec<Notification>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NotificationQuery {
+    /// Only unread notifications.
+    #[serde(default)]
+    pub unread: bool,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, ToSchema)]
+pub struct MarkRead {
+    /// Notifications to mark as read; all of them when omitted.
+    pub ids: Option<Vec<Uuid>>,
+}
+
+/// Which events notify the user. Everything notifies by default, and kinds
+/// left out of an update keep notifying.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+#[serde(default)]
+pub struct NotificationPreferences {
+    pub answer: bool,
+    pub accepted: bool,
+}
+
+impl Default for NotificationPreferences {
+    fn default() -> Self {
+        Self {
+            answer: true,
+            accepted: true,
+        }
+    }
+}
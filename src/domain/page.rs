@@ -0,0 +1,82 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use super::{feed::FeedItem, question::Bookmark, reputation::ReputationEvent};
+
+pub const DEFAULT_LIMIT: i64 = 20;
+pub const MAX_LIMIT: i64 = 100;
+
+/// Query of a paginated list, ordered newest first.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PageQuery {
+    pub limit: Option<i64>,
+    /// `next` of the previous page.
+    pub before: Option<String>,
+}
+
+impl PageQuery {
+    pub fn limit(&self) -> i64 {
+        self.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT)
+    }
+}
+
+/// One page of a list. Keyset pagination keeps pages stable while new items
+/// arrive at the top.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[aliases(
+    FeedPage = Page<FeedItem>,
+    BookmarkPage = Page<Bookmark>,
+    ReputationPage = Page<ReputationEvent>
+)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Pass as `before` to get the next page; absent on the last one.
+    pub next: Option<String>,
+}
+
+/// Position in a list ordered by `(created_on, id)` descending, written as
+/// `<unix micros>_<id>`.
+#[derive(Debug, Clone, Copy)]
+pub struct Cursor {
+    pub created_on: NaiveDateTime,
+    pub id: Uuid,
+}
+
+impl Cursor {
+    pub fn parse(value: &str) -> Option<Self> {
+        let (micros, id) = value.split_once('_')?;
+        Some(Self {
+            created_on: chrono::DateTime::from_timestamp_micros(micros.parse().ok()?)?.naive_utc(),
+            id: id.parse().ok()?,
+        })
+    }
+}
+
+impl std::fmt::Display for Cursor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}_{}",
+            self.created_on.and_utc().timestamp_micros(),
+            self.id
+        )
+    }
+}
+
+impl<T> Page<T> {
+    /// Builds a page from up to `limit + 1` rows; the extra row only tells
+    /// that another page follows.
+    pub fn new(mut items: Vec<T>, limit: i64, cursor: impl Fn(&T) -> Cursor) -> Self {
+        let next = match items.len() as i64 > limit {
+            true => {
+                items.truncate(limit as usize);
+                items.last().map(|item| cursor(item).to_string())
+            }
+            false => None,
+        };
+
+        Self { items, next }
+    }
+}
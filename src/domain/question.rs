@@ -1,17 +1,110 @@
+use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
 use uuid::Uuid;
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, FromRow, ToSchema)]
 pub struct Question {
     pub id: Uuid,
     pub title: String,
     pub content: String,
+    /// `content` rendered from Markdown and sanitized.
+    pub content_html: String,
     pub tags: Option<Vec<String>>,
+    pub version: i32,
+    pub status: QuestionStatus,
+    /// Why the question was closed or locked.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub close_reason: Option<CloseReason>,
+    /// The question this one duplicates, when closed as a duplicate.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duplicate_of: Option<Uuid>,
+    /// Whether the caller bookmarked the question; only present when the
+    /// request is authenticated.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bookmarked: Option<bool>,
+}
+
+/// A question in the caller's bookmarks.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct Bookmark {
+    pub bookmarked_on: NaiveDateTime,
+    pub question: Question,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct QuestionSummary {
+    pub id: Uuid,
+    pub title: String,
+    pub user_id: Option<Uuid>,
+    pub created_on: NaiveDateTime,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, ToSchema)]
 pub struct NewQuestion {
     pub title: String,
     pub content: String,
     pub tags: Option<Vec<String>>,
 }
+
+/// Only open questions take answers. Closed questions can be reopened by a
+/// moderator; locked ones are closed by a moderator and also frozen against
+/// edits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "question_status", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum QuestionStatus {
+    Open,
+    Closed,
+    Locked,
+}
+
+impl QuestionStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QuestionStatus::Open => "open",
+            QuestionStatus::Closed => "closed",
+            QuestionStatus::Locked => "locked",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "close_reason", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum CloseReason {
+    /// Needs `duplicate_of`.
+    Duplicate,
+    OffTopic,
+    Unclear,
+    TooBroad,
+    OpinionBased,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct NewCloseVote {
+    pub reason: CloseReason,
+    /// The original question; required with `duplicate` and not allowed
+    /// otherwise.
+    pub duplicate_of: Option<Uuid>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct CloseVotes {
+    /// Votes to close, counting the one that closed the question if any.
+    pub votes: i64,
+    /// Votes that close the question.
+    pub needed: i64,
+    pub status: QuestionStatus,
+}
+
+/// A moderator's status change.
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct StatusChange {
+    pub status: QuestionStatus,
+    /// Required to close, optional to lock and not allowed to reopen.
+    pub reason: Option<CloseReason>,
+    /// Required with the `duplicate` reason and not allowed otherwise.
+    pub duplicate_of: Option<Uuid>,
+}
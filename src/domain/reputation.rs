@@ -0,0 +1,126 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy)]
+pub enum VoteTarget {
+    Question(Uuid),
+    Answer(Uuid),
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct NewVote {
+    /// `1` for an upvote, `-1` for a downvote.
+    pub value: i16,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct VoteResult {
+    /// Sum of all votes on the post.
+    pub score: i64,
+    /// The caller's vote, if any.
+    pub vote: Option<i16>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "reputation_reason", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum ReputationReason {
+    Upvote,
+    Downvote,
+    /// Cancels the points of a retracted or changed vote.
+    VoteUndone,
+    Accepted,
+    /// Cancels the points of an acceptance that was withdrawn or moved.
+    AcceptUndone,
+}
+
+/// One entry of a user's reputation ledger.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ReputationEvent {
+    pub id: Uuid,
+    pub delta: i32,
+    pub reason: ReputationReason,
+    /// The user who voted or accepted.
+    pub actor_id: Option<Uuid>,
+    pub question_id: Option<Uuid>,
+    pub answer_id: Option<Uuid>,
+    pub created_on: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "badge_kind", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum BadgeKind {
+    /// Posted a first answer.
+    FirstAnswer,
+    /// Had ten answers accepted.
+    TenAccepted,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct Badge {
+    pub badge: BadgeKind,
+    pub awarded_on: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct UserProfile {
+    pub id: Uuid,
+    pub name: String,
+    pub reputation: i64,
+    pub badges: Vec<Badge>,
+}
+
+/// The author of a post as shown next to it.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct AuthorSummary {
+    pub id: Uuid,
+    pub name: String,
+    pub reputation: i64,
+    pub badges: Vec<BadgeKind>,
+}
+
+/// Actions that need a minimum reputation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Privilege {
+    Downvote,
+    EditOthers,
+    CreateTags,
+    VoteToClose,
+}
+
+impl Privilege {
+    pub const ALL: [Privilege; 4] = [
+        Privilege::Downvote,
+        Privilege::EditOthers,
+        Privilege::CreateTags,
+        Privilege::VoteToClose,
+    ];
+
+    /// What the privilege allows, as the subject of a sentence.
+    pub fn describe(&self) -> &'static str {
+        match self {
+            Privilege::Downvote => "Downvoting",
+            Privilege::EditOthers => "Editing other users' posts",
+            Privilege::CreateTags => "Creating new tags",
+            Privilege::VoteToClose => "Voting to close questions",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct PrivilegeStatus {
+    pub privilege: Privilege,
+    /// Reputation needed.
+    pub required: i64,
+    pub granted: bool,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct Privileges {
+    pub reputation: i64,
+    pub privileges: Vec<PrivilegeStatus>,
+}
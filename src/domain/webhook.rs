@@ -0,0 +1,89 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Events a webhook can subscribe to, named `<resource>.<action>` on the wire.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub enum WebhookEvent {
+    #[serde(rename = "question.created")]
+    QuestionCreated,
+    #[serde(rename = "question.updated")]
+    QuestionUpdated,
+    #[serde(rename = "question.deleted")]
+    QuestionDeleted,
+    #[serde(rename = "answer.created")]
+    AnswerCreated,
+    #[serde(rename = "answer.deleted")]
+    AnswerDeleted,
+    #[serde(rename = "answer.accepted")]
+    AnswerAccepted,
+}
+
+impl WebhookEvent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WebhookEvent::QuestionCreated => "question.created",
+            WebhookEvent::QuestionUpdated => "question.updated",
+            WebhookEvent::QuestionDeleted => "question.deleted",
+            WebhookEvent::AnswerCreated => "answer.created",
+            WebhookEvent::AnswerDeleted => "answer.deleted",
+            WebhookEvent::AnswerAccepted => "answer.accepted",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct Webhook {
+    pub id: Uuid,
+    pub url: String,
+    pub events: Vec<String>,
+    /// Only returned when the webhook is created.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+    pub created_on: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct NewWebhook {
+    pub url: String,
+    pub events: Vec<WebhookEvent>,
+    /// Key for the `X-Askly-Signature` HMAC; generated when omitted.
+    pub secret: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "delivery_status", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryStatus {
+    Pending,
+    Delivered,
+    /// Gave up after the last retry.
+    Failed,
+}
+
+/// One entry of a webhook's delivery log.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct WebhookDelivery {
+    pub id: Uuid,
+    pub event: String,
+    pub status: DeliveryStatus,
+    pub attempts: i32,
+    /// HTTP status of the last attempt, if the receiver answered.
+    pub response_status: Option<i32>,
+    pub error: Option<String>,
+    pub next_attempt_on: NaiveDateTime,
+    pub created_on: NaiveDateTime,
+    pub delivered_on: Option<NaiveDateTime>,
+}
+
+/// A delivery claimed by the worker, with what it needs to send it.
+#[derive(Debug, Clone)]
+pub struct PendingDelivery {
+    pub id: Uuid,
+    pub url: String,
+    pub secret: String,
+    pub event: String,
+    pub payload: String,
+    pub attempts: i32,
+}
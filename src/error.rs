@@ -1,63 +1,250 @@
-use std::convert::Infallible;
+use std::fmt;
 
+use serde::Serialize;
+use utoipa::ToSchema;
 use warp::{
-    http::StatusCode,
+    http::{HeaderValue, StatusCode},
     reject::{Reject, Rejection},
-    reply::Reply,
+    reply::{Reply, Response},
 };
 
+use crate::domain::{question::QuestionStatus, reputation::Privilege};
+
 #[derive(Debug)]
 pub enum Error {
     DbError(sqlx::Error),
     InvalidEmailPassword,
+    AccountDisabled,
     ServerError,
     JwtError,
     NotOwner,
+    NotFound,
+    PreconditionFailed,
+    PreconditionRequired,
+    RateLimited,
+    StorageError(String),
+    PayloadTooLarge,
+    UnsupportedMediaType,
+    InvalidUpload,
+    AdminOnly,
+    ModeratorOnly,
+    InvalidInput(String),
+    QuestionNotOpen(QuestionStatus),
+    MissingPrivilege {
+        privilege: Privilege,
+        required: i64,
+        reputation: i64,
+    },
 }
 
 impl Reject for Error {}
 
-pub async fn handle_rejection(err: Rejection) -> Result<impl Reply, Infallible> {
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::DbError(e) => write!(f, "database error: {}", e),
+            Error::InvalidEmailPassword => write!(f, "invalid email or password"),
+            Error::AccountDisabled => write!(f, "account is disabled"),
+            Error::ServerError => write!(f, "internal server error"),
+            Error::JwtError => write!(f, "invalid token"),
+            Error::NotOwner => write!(f, "not the owner"),
+            Error::NotFound => write!(f, "not found"),
+            Error::PreconditionFailed => write!(f, "resource was modified"),
+            Error::PreconditionRequired => write!(f, "If-Match header is required"),
+            Error::RateLimited => write!(f, "too many requests"),
+            Error::StorageError(e) => write!(f, "storage error: {}", e),
+            Error::PayloadTooLarge => write!(f, "attachment is too large"),
+            Error::UnsupportedMediaType => write!(f, "attachment type is not allowed"),
+            Error::InvalidUpload => write!(f, "expected a multipart body with a `file` part"),
+            Error::AdminOnly => write!(f, "only admins may do this"),
+            Error::ModeratorOnly => write!(f, "only moderators may do this"),
+            Error::InvalidInput(e) => write!(f, "invalid input: {}", e),
+            Error::QuestionNotOpen(status) => write!(f, "question is {}", status.as_str()),
+            Error::MissingPrivilege {
+                privilege,
+                required,
+                reputation,
+            } => write!(
+                f,
+                "{} requires {} reputation; you have {}",
+                privilege.describe(),
+                required,
+                reputation
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Body of every error response.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ErrorBody<'a> {
+    message: &'a str,
+    request_id: &'a str,
+}
+
+fn error_reply(message: &str, status: StatusCode, request_id: &str) -> Response {
+    let body = ErrorBody {
+        message,
+        request_id,
+    };
+
+    warp::reply::with_status(warp::reply::json(&body), status).into_response()
+}
+
+/// Finishes every request: rejections become JSON error bodies and the
+/// request id is echoed back in `X-Request-Id`.
+pub async fn respond(request_id: String, result: Result<Response, Rejection>) -> Response {
+    let mut response = match result {
+        Ok(response) => response,
+        Err(err) => handle_rejection(err, &request_id),
+    };
+
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        response.headers_mut().insert("x-request-id", value);
+    }
+
+    response
+}
+
+pub fn handle_rejection(err: Rejection, request_id: &str) -> Response {
     if let Some(Error::InvalidEmailPassword) = err.find() {
-        return Ok(warp::reply::with_status(
-            "Invalid email or password".to_string(),
+        return error_reply(
+            "Invalid email or password",
             StatusCode::UNAUTHORIZED,
-        ));
+            request_id,
+        );
+    }
+
+    if let Some(Error::AccountDisabled) = err.find() {
+        return error_reply("Account is disabled", StatusCode::FORBIDDEN, request_id);
     }
 
     if let Some(Error::DbError(e)) = err.find() {
-        // TODO: handle Db errors
-        eprintln!("{}", e);
+        tracing::error!("database error: {}", e);
+
+        return error_reply(
+            "Internal Server Error",
+            StatusCode::INTERNAL_SERVER_ERROR,
+            request_id,
+        );
+    }
+
+    if let Some(Error::StorageError(e)) = err.find() {
+        tracing::error!("storage error: {}", e);
 
-        return Ok(warp::reply::with_status(
-            "Internal Server Error".to_string(),
+        return error_reply(
+            "Internal Server Error",
             StatusCode::INTERNAL_SERVER_ERROR,
-        ));
+            request_id,
+        );
     }
 
     if let Some(Error::ServerError) = err.find() {
-        return Ok(warp::reply::with_status(
-            "Internal Server Error".to_string(),
+        return error_reply(
+            "Internal Server Error",
             StatusCode::INTERNAL_SERVER_ERROR,
-        ));
+            request_id,
+        );
     }
 
     if let Some(Error::JwtError) = err.find() {
-        return Ok(warp::reply::with_status(
-            "Unauthorized".to_string(),
-            StatusCode::UNAUTHORIZED,
-        ));
+        return error_reply("Unauthorized", StatusCode::UNAUTHORIZED, request_id);
     }
 
     if let Some(Error::NotOwner) = err.find() {
-        return Ok(warp::reply::with_status(
-            "Unauthorized".to_string(),
+        return error_reply("Unauthorized", StatusCode::FORBIDDEN, request_id);
+    }
+
+    if let Some(Error::NotFound) = err.find() {
+        return error_reply("Not found", StatusCode::NOT_FOUND, request_id);
+    }
+
+    if let Some(Error::PreconditionFailed) = err.find() {
+        return error_reply(
+            "Resource was modified",
+            StatusCode::PRECONDITION_FAILED,
+            request_id,
+        );
+    }
+
+    if let Some(Error::PreconditionRequired) = err.find() {
+        return error_reply(
+            "If-Match header is required",
+            StatusCode::PRECONDITION_REQUIRED,
+            request_id,
+        );
+    }
+
+    if let Some(Error::RateLimited) = err.find() {
+        return error_reply(
+            "Too many requests",
+            StatusCode::TOO_MANY_REQUESTS,
+            request_id,
+        );
+    }
+
+    if let Some(Error::PayloadTooLarge) = err.find() {
+        return error_reply(
+            "Attachment is too large",
+            StatusCode::PAYLOAD_TOO_LARGE,
+            request_id,
+        );
+    }
+
+    // Raised by `warp::multipart` when the whole body exceeds its limit.
+    if err.find::<warp::reject::PayloadTooLarge>().is_some() {
+        return error_reply(
+            "Attachment is too large",
+            StatusCode::PAYLOAD_TOO_LARGE,
+            request_id,
+        );
+    }
+
+    if let Some(Error::UnsupportedMediaType) = err.find() {
+        return error_reply(
+            "Attachment type is not allowed",
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            request_id,
+        );
+    }
+
+    if let Some(Error::InvalidUpload) = err.find() {
+        return error_reply(
+            "Expected a multipart body with a `file` part",
+            StatusCode::BAD_REQUEST,
+            request_id,
+        );
+    }
+
+    if let Some(Error::AdminOnly) = err.find() {
+        return error_reply("Only admins may do this", StatusCode::FORBIDDEN, request_id);
+    }
+
+    if let Some(Error::ModeratorOnly) = err.find() {
+        return error_reply(
+            "Only moderators may do this",
             StatusCode::FORBIDDEN,
-        ));
+            request_id,
+        );
+    }
+
+    if let Some(Error::QuestionNotOpen(status)) = err.find() {
+        return error_reply(
+            &format!("This question is {}", status.as_str()),
+            StatusCode::CONFLICT,
+            request_id,
+        );
+    }
+
+    if let Some(e @ Error::MissingPrivilege { .. }) = err.find() {
+        return error_reply(&e.to_string(), StatusCode::FORBIDDEN, request_id);
+    }
+
+    if let Some(Error::InvalidInput(e)) = err.find() {
+        return error_reply(e, StatusCode::BAD_REQUEST, request_id);
     }
 
-    Ok(warp::reply::with_status(
-        "Route not found".to_string(),
-        StatusCode::NOT_FOUND,
-    ))
+    error_reply("Route not found", StatusCode::NOT_FOUND, request_id)
 }
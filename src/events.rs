@@ -0,0 +1,172 @@
+//! Live question events, shared across instances through Postgres
+//! `LISTEN/NOTIFY`.
+
+use std::time::Duration;
+
+use sqlx::postgres::PgListener;
+use tokio::{sync::broadcast, task::JoinHandle};
+use tracing::{debug, warn};
+
+use crate::{
+    domain::{
+        event::{Change, Event},
+        reputation::VoteTarget,
+    },
+    error::Error,
+    shutdown::Shutdown,
+    store::{DbStore, EVENTS_CHANNEL},
+};
+
+/// Events a slow subscriber may fall behind by before it starts missing some.
+const CAPACITY: usize = 256;
+
+/// In-process fan-out of [`Event`]s to every open stream.
+///
+/// Store mutations notify [`EVENTS_CHANNEL`] when they commit; each instance
+/// listens on it and loads the changed rows once, so subscribers see changes
+/// made through any instance.
+#[derive(Debug, Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<Event>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        let (sender, _) = broadcast::channel(CAPACITY);
+        Self { sender }
+    }
+}
+
+impl EventBus {
+    /// A bus nothing feeds; see [`EventBus::listen`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates the bus and spawns the task that feeds it from Postgres until
+    /// `shutdown` fires.
+    pub fn listen(store: DbStore, shutdown: Shutdown) -> (Self, JoinHandle<()>) {
+        let bus = Self::new();
+        let task = tokio::spawn(bus.clone().forward(store, shutdown));
+
+        (bus, task)
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Event> {
+        self.sender.subscribe()
+    }
+
+    async fn forward(self, store: DbStore, mut shutdown: Shutdown) {
+        tokio::select! {
+            _ = self.forward_changes(store) => {}
+            _ = shutdown.wait() => debug!("event listener stopped"),
+        }
+    }
+
+    async fn forward_changes(&self, store: DbStore) {
+        loop {
+            let mut listener = match PgListener::connect_with(&store.conn).await {
+                Ok(listener) => listener,
+                Err(e) => {
+                    warn!("event listener failed to connect: {}", e);
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                    continue;
+                }
+            };
+
+            if let Err(e) = listener.listen(EVENTS_CHANNEL).await {
+                warn!("event listener failed to listen: {}", e);
+                tokio::time::sleep(Duration::from_secs(1)).await;
+                continue;
+            }
+
+            // `recv` reconnects on its own after a dropped connection;
+            // notifications sent in between are lost.
+            loop {
+                let notification = match listener.recv().await {
+                    Ok(notification) => notification,
+                    Err(e) => {
+                        warn!("event listener failed: {}", e);
+                        break;
+                    }
+                };
+
+                let change = match serde_json::from_str::<Change>(notification.payload()) {
+                    Ok(change) => change,
+                    Err(e) => {
+                        warn!("ignoring malformed event: {}", e);
+                        continue;
+                    }
+                };
+
+                // Nobody is subscribed on this instance.
+                if self.sender.receiver_count() == 0 {
+                    continue;
+                }
+
+                match load(&store, change).await {
+                    Ok(event) => {
+                        self.sender.send(event).ok();
+                    }
+                    // Deleted again before it could be loaded; the deletion
+                    // follows as its own event.
+                    Err(Error::NotFound) => debug!("changed row is gone"),
+                    Err(e) => warn!("failed to load event: {}", e),
+                }
+            }
+
+            tokio::time::sleep(Duration::from_secs(1)).await;
+        }
+    }
+}
+
+async fn load(store: &DbStore, change: Change) -> Result<Event, Error> {
+    Ok(match change {
+        Change::AnswerAdded {
+            question_id,
+            answer_id,
+        } => Event::AnswerAdded {
+            question_id,
+            answer: store.get_answer(answer_id).await?,
+        },
+        Change::AnswerDeleted {
+            question_id,
+            answer_id,
+        } => Event::AnswerDeleted {
+            question_id,
+            answer_id,
+        },
+        Change::QuestionUpdated { question_id } => Event::QuestionUpdated {
+            question_id,
+            question: store.get_question(question_id).await?,
+        },
+        Change::QuestionDeleted { question_id } => Event::QuestionDeleted { question_id },
+        Change::Voted {
+            question_id,
+            answer_id,
+        } => Event::Voted {
+            question_id,
+            answer_id,
+            score: store
+                .get_score(match answer_id {
+                    Some(answer_id) => VoteTarget::Answer(answer_id),
+                    None => VoteTarget::Question(question_id),
+                })
+                .await?,
+        },
+        Change::AnswerAccepted {
+            question_id,
+            answer_id,
+        } => Event::AnswerAccepted {
+            question_id,
+            answer_id,
+        },
+        Change::AnswerUnaccepted {
+            question_id,
+            answer_id,
+        } => Event::AnswerUnaccepted {
+            question_id,
+            answer_id,
+        },
+    })
+}
@@ -1,23 +1,150 @@
+use std::future;
+
 use uuid::Uuid;
-use warp::{filters::BoxedFilter, Filter, Reply};
+use warp::{filters::BoxedFilter, reject::Rejection, reply::Response, Filter, Reply};
 
+pub mod config;
 pub mod domain;
 pub mod error;
+pub mod events;
+pub mod markdown;
+pub mod metrics;
+pub mod migrate;
 pub mod routes;
+pub mod shutdown;
+pub mod storage;
 pub mod store;
+pub mod telemetry;
+pub mod webhooks;
 
+use config::Config;
+use domain::reputation::Privilege;
+use events::EventBus;
+use metrics::Metrics;
 use store::DbStore;
 
-pub async fn build_routes(store: DbStore) -> BoxedFilter<(impl Reply,)> {
-    let db_store = warp::any().map(move || store.clone());
+/// The whole HTTP API. Background tasks such as the [`EventBus`] listener and
+/// the [`webhooks`] worker are started by the caller, once per process.
+pub async fn build_routes(
+    store: DbStore,
+    config: Config,
+    events: EventBus,
+) -> BoxedFilter<(impl Reply,)> {
+    let db_store = {
+        let store = store.clone();
+        warp::any().map(move || store.clone())
+    };
+
+    let metrics = Metrics::new();
+    let app_metrics = {
+        let metrics = metrics.clone();
+        warp::any().map(move || metrics.clone())
+    };
+
+    let rate_limit = routes::rate_limit(config.rate_limit_per_minute);
+
+    let hello = warp::get().and(warp::path::end()).and_then(routes::hello);
+
+    let healthz = warp::get()
+        .and(warp::path("healthz"))
+        .and(warp::path::end())
+        .and_then(routes::healthz);
+
+    let readyz = warp::get()
+        .and(warp::path("readyz"))
+        .and(warp::path::end())
+        .and(db_store.clone())
+        .and_then(routes::readyz);
+
+    let version = warp::get()
+        .and(warp::path("version"))
+        .and(warp::path::end())
+        .and_then(routes::version);
+
+    let get_metrics = warp::get()
+        .and(warp::path("metrics"))
+        .and(warp::path::end())
+        .and(app_metrics)
+        .and(db_store)
+        .and_then(routes::metrics);
 
-    let hello = warp::get()
+    let openapi_json = warp::get()
+        .and(warp::path("openapi.json"))
         .and(warp::path::end())
-        .map(|| format!("Hello, world!"));
+        .and_then(routes::openapi_json);
+
+    let docs = warp::get()
+        .and(warp::path("docs"))
+        .and(warp::path::end())
+        .and_then(routes::docs);
+
+    let v1 = api_v1(store, &config, metrics.clone(), events);
+
+    // Probes and scrapes are exempt from rate limiting. The root aliases of
+    // `/v1` routes are deprecated and answer with `Deprecation` headers.
+    let routes = healthz
+        .or(readyz)
+        .or(version)
+        .or(get_metrics)
+        .or(rate_limit.and(
+            hello
+                .or(openapi_json)
+                .or(docs)
+                .or(warp::path("v1").and(v1.clone()))
+                .or(routes::legacy_alias().and(v1)),
+        ))
+        .map(|reply| Ok(Reply::into_response(reply)))
+        .or_else(|err| future::ready(Ok::<_, Rejection>((Err(err),))));
+
+    let api = routes::request_id().and(routes).then(error::respond);
+    let api = warp::path::full().and(api).map(routes::deprecate_legacy);
+
+    let observe = warp::log::custom(move |info| metrics.observe(info));
+
+    match routes::cors(&config) {
+        Some(cors) => api
+            .with(cors)
+            .map(Reply::into_response)
+            .with(observe)
+            .with(warp::trace(routes::request_span))
+            .boxed(),
+        None => api
+            .with(observe)
+            .with(warp::trace(routes::request_span))
+            .boxed(),
+    }
+}
+
+/// Version 1 of the API, mounted under `/v1`. A later version gets its own
+/// function next to this one and shares the store and domain types.
+fn api_v1(
+    store: DbStore,
+    config: &Config,
+    metrics: Metrics,
+    events: EventBus,
+) -> BoxedFilter<(Response,)> {
+    let db_store = {
+        let store = store.clone();
+        warp::any().map(move || store.clone())
+    };
+    let app_metrics = warp::any().map(move || metrics.clone());
+    let app_events = warp::any().map(move || events.clone());
+    let app_config = {
+        let config = config.clone();
+        warp::any().map(move || config.clone())
+    };
+    let app_storage = {
+        let storage = storage::from_config(&config.storage);
+        warp::any().map(move || storage.clone())
+    };
+
+    // Multipart framing on top of the largest accepted file.
+    let upload = warp::multipart::form().max_length(config.attachment_max_bytes + 64 * 1024);
 
     let get_questions = warp::get()
         .and(warp::path("questions"))
         .and(warp::path::end())
+        .and(routes::optional_auth(config, &store))
         .and(db_store.clone())
         .and_then(routes::get_questions);
 
@@ -25,14 +152,18 @@ pub async fn build_routes(store: DbStore) -> BoxedFilter<(impl Reply,)> {
         .and(warp::path("questions"))
         .and(warp::path::param::<Uuid>())
         .and(warp::path::end())
+        .and(routes::if_none_match())
+        .and(routes::optional_auth(config, &store))
         .and(db_store.clone())
         .and_then(routes::get_question);
 
     let add_question = warp::post()
         .and(warp::path("questions"))
         .and(warp::path::end())
-        .and(routes::protect())
+        .and(routes::protect(config, &store))
         .and(db_store.clone())
+        .and(app_config.clone())
+        .and(app_metrics.clone())
         .and(warp::body::json())
         .and_then(routes::add_question);
 
@@ -40,8 +171,10 @@ pub async fn build_routes(store: DbStore) -> BoxedFilter<(impl Reply,)> {
         .and(warp::path("questions"))
         .and(warp::path::param::<Uuid>())
         .and(warp::path::end())
-        .and(routes::protect())
+        .and(routes::protect(config, &store))
+        .and(routes::if_match())
         .and(db_store.clone())
+        .and(app_config.clone())
         .and(warp::body::json())
         .and_then(routes::update_question);
 
@@ -49,10 +182,63 @@ pub async fn build_routes(store: DbStore) -> BoxedFilter<(impl Reply,)> {
         .and(warp::path("questions"))
         .and(warp::path::param::<Uuid>())
         .and(warp::path::end())
-        .and(routes::protect())
+        .and(routes::protect(config, &store))
+        .and(routes::if_match())
         .and(db_store.clone())
+        .and(app_storage.clone())
         .and_then(routes::delete_question);
 
+    // A WebSocket upgrade takes precedence; plain requests get SSE.
+    let question_events_ws = warp::get()
+        .and(warp::path("questions"))
+        .and(warp::path::param::<Uuid>())
+        .and(warp::path("events"))
+        .and(warp::path::end())
+        .and(warp::ws())
+        .and(db_store.clone())
+        .and(app_events.clone())
+        .and_then(routes::question_events_ws);
+
+    let question_events = warp::get()
+        .and(warp::path("questions"))
+        .and(warp::path::param::<Uuid>())
+        .and(warp::path("events"))
+        .and(warp::path::end())
+        .and(db_store.clone())
+        .and(app_events)
+        .and_then(routes::question_events);
+
+    let add_close_vote = warp::put()
+        .and(warp::path("questions"))
+        .and(warp::path::param::<Uuid>())
+        .and(warp::path("close-vote"))
+        .and(warp::path::end())
+        .and(routes::privileged(Privilege::VoteToClose, config, &store))
+        .and(db_store.clone())
+        .and(app_config.clone())
+        .and(warp::body::json())
+        .and_then(routes::add_close_vote);
+
+    let delete_close_vote = warp::delete()
+        .and(warp::path("questions"))
+        .and(warp::path::param::<Uuid>())
+        .and(warp::path("close-vote"))
+        .and(warp::path::end())
+        .and(routes::protect(config, &store))
+        .and(db_store.clone())
+        .and(app_config.clone())
+        .and_then(routes::delete_close_vote);
+
+    let set_question_status = warp::put()
+        .and(warp::path("questions"))
+        .and(warp::path::param::<Uuid>())
+        .and(warp::path("status"))
+        .and(warp::path::end())
+        .and(routes::protect(config, &store))
+        .and(db_store.clone())
+        .and(warp::body::json())
+        .and_then(routes::set_question_status);
+
     let get_answers = warp::get()
         .and(warp::path("questions"))
         .and(warp::path::param::<Uuid>())
@@ -64,8 +250,9 @@ pub async fn build_routes(store: DbStore) -> BoxedFilter<(impl Reply,)> {
         .and(warp::path("questions"))
         .and(warp::path::param::<Uuid>())
         .and(warp::path("answers"))
-        .and(routes::protect())
+        .and(routes::protect(config, &store))
         .and(db_store.clone())
+        .and(app_metrics.clone())
         .and(warp::body::json())
         .and_then(routes::add_answer);
 
@@ -73,10 +260,63 @@ pub async fn build_routes(store: DbStore) -> BoxedFilter<(impl Reply,)> {
         .and(warp::path("answers"))
         .and(warp::path::param::<Uuid>())
         .and(warp::path::end())
-        .and(routes::protect())
+        .and(routes::protect(config, &store))
+        .and(routes::if_match())
         .and(db_store.clone())
+        .and(app_storage.clone())
         .and_then(routes::delete_answer);
 
+    let add_question_attachment = warp::post()
+        .and(warp::path("questions"))
+        .and(warp::path::param::<Uuid>())
+        .and(warp::path("attachments"))
+        .and(warp::path::end())
+        .and(routes::protect(config, &store))
+        .and(db_store.clone())
+        .and(app_storage.clone())
+        .and(app_config.clone())
+        .and(upload.clone())
+        .and_then(routes::add_question_attachment);
+
+    let add_answer_attachment = warp::post()
+        .and(warp::path("answers"))
+        .and(warp::path::param::<Uuid>())
+        .and(warp::path("attachments"))
+        .and(warp::path::end())
+        .and(routes::protect(config, &store))
+        .and(db_store.clone())
+        .and(app_storage.clone())
+        .and(app_config.clone())
+        .and(upload)
+        .and_then(routes::add_answer_attachment);
+
+    let get_question_attachments = warp::get()
+        .and(warp::path("questions"))
+        .and(warp::path::param::<Uuid>())
+        .and(warp::path("attachments"))
+        .and(warp::path::end())
+        .and(routes::protect(config, &store))
+        .and(db_store.clone())
+        .and_then(routes::get_question_attachments);
+
+    let get_answer_attachments = warp::get()
+        .and(warp::path("answers"))
+        .and(warp::path::param::<Uuid>())
+        .and(warp::path("attachments"))
+        .and(warp::path::end())
+        .and(routes::protect(config, &store))
+        .and(db_store.clone())
+        .and_then(routes::get_answer_attachments);
+
+    let get_attachment = warp::get()
+        .and(warp::path("attachments"))
+        .and(warp::path::param::<Uuid>())
+        .and(warp::path::end())
+        .and(routes::protect(config, &store))
+        .and(db_store.clone())
+        .and(app_storage)
+        .and_then(routes::get_attachment);
+
     let signup = warp::post()
         .and(warp::path("signup"))
         .and(warp::path::end())
@@ -88,21 +328,346 @@ pub async fn build_routes(store: DbStore) -> BoxedFilter<(impl Reply,)> {
         .and(warp::path("signin"))
         .and(warp::path::end())
         .and(db_store.clone())
+        .and(app_config.clone())
+        .and(app_metrics.clone())
         .and(warp::body::json())
         .and_then(routes::signin);
 
-    hello
-        .or(get_questions)
+    let preview = warp::post()
+        .and(warp::path("preview"))
+        .and(warp::path::end())
+        .and(warp::body::json())
+        .and_then(routes::preview);
+
+    let get_notifications = warp::get()
+        .and(warp::path("me"))
+        .and(warp::path("notifications"))
+        .and(warp::path::end())
+        .and(routes::protect(config, &store))
+        .and(db_store.clone())
+        .and(warp::query())
+        .and_then(routes::get_notifications);
+
+    let mark_notifications_read = warp::post()
+        .and(warp::path("me"))
+        .and(warp::path("notifications"))
+        .and(warp::path("read"))
+        .and(warp::path::end())
+        .and(routes::protect(config, &store))
+        .and(db_store.clone())
+        .and(warp::body::json())
+        .and_then(routes::mark_notifications_read);
+
+    let get_notification_preferences = warp::get()
+        .and(warp::path("me"))
+        .and(warp::path("notification-preferences"))
+        .and(warp::path::end())
+        .and(routes::protect(config, &store))
+        .and(db_store.clone())
+        .and_then(routes::get_notification_preferences);
+
+    let set_notification_preferences = warp::put()
+        .and(warp::path("me"))
+        .and(warp::path("notification-preferences"))
+        .and(warp::path::end())
+        .and(routes::protect(config, &store))
+        .and(db_store.clone())
+        .and(warp::body::json())
+        .and_then(routes::set_notification_preferences);
+
+    let add_bookmark = warp::put()
+        .and(warp::path("questions"))
+        .and(warp::path::param::<Uuid>())
+        .and(warp::path("bookmark"))
+        .and(warp::path::end())
+        .and(routes::protect(config, &store))
+        .and(db_store.clone())
+        .and_then(routes::add_bookmark);
+
+    let delete_bookmark = warp::delete()
+        .and(warp::path("questions"))
+        .and(warp::path::param::<Uuid>())
+        .and(warp::path("bookmark"))
+        .and(warp::path::end())
+        .and(routes::protect(config, &store))
+        .and(db_store.clone())
+        .and_then(routes::delete_bookmark);
+
+    let get_bookmarks = warp::get()
+        .and(warp::path("me"))
+        .and(warp::path("bookmarks"))
+        .and(warp::path::end())
+        .and(routes::protect(config, &store))
+        .and(db_store.clone())
+        .and(warp::query())
+        .and_then(routes::get_bookmarks);
+
+    let follow_question = warp::put()
+        .and(warp::path("questions"))
+        .and(warp::path::param::<Uuid>())
+        .and(warp::path("follow"))
+        .and(warp::path::end())
+        .and(routes::protect(config, &store))
+        .and(db_store.clone())
+        .and_then(routes::follow_question);
+
+    let unfollow_question = warp::delete()
+        .and(warp::path("questions"))
+        .and(warp::path::param::<Uuid>())
+        .and(warp::path("follow"))
+        .and(warp::path::end())
+        .and(routes::protect(config, &store))
+        .and(db_store.clone())
+        .and_then(routes::unfollow_question);
+
+    let follow_tag = warp::put()
+        .and(warp::path("tags"))
+        .and(warp::path::param::<String>())
+        .and(warp::path("follow"))
+        .and(warp::path::end())
+        .and(routes::protect(config, &store))
+        .and(db_store.clone())
+        .and_then(routes::follow_tag);
+
+    let unfollow_tag = warp::delete()
+        .and(warp::path("tags"))
+        .and(warp::path::param::<String>())
+        .and(warp::path("follow"))
+        .and(warp::path::end())
+        .and(routes::protect(config, &store))
+        .and(db_store.clone())
+        .and_then(routes::unfollow_tag);
+
+    let follow_user = warp::put()
+        .and(warp::path("users"))
+        .and(warp::path::param::<Uuid>())
+        .and(warp::path("follow"))
+        .and(warp::path::end())
+        .and(routes::protect(config, &store))
+        .and(db_store.clone())
+        .and_then(routes::follow_user);
+
+    let unfollow_user = warp::delete()
+        .and(warp::path("users"))
+        .and(warp::path::param::<Uuid>())
+        .and(warp::path("follow"))
+        .and(warp::path::end())
+        .and(routes::protect(config, &store))
+        .and(db_store.clone())
+        .and_then(routes::unfollow_user);
+
+    let get_follows = warp::get()
+        .and(warp::path("me"))
+        .and(warp::path("follows"))
+        .and(warp::path::end())
+        .and(routes::protect(config, &store))
+        .and(db_store.clone())
+        .and_then(routes::get_follows);
+
+    let get_feed = warp::get()
+        .and(warp::path("me"))
+        .and(warp::path("feed"))
+        .and(warp::path::end())
+        .and(routes::protect(config, &store))
+        .and(db_store.clone())
+        .and(warp::query())
+        .and_then(routes::get_feed);
+
+    let vote_question = warp::put()
+        .and(warp::path("questions"))
+        .and(warp::path::param::<Uuid>())
+        .and(warp::path("vote"))
+        .and(warp::path::end())
+        .and(routes::protect(config, &store))
+        .and(db_store.clone())
+        .and(app_config.clone())
+        .and(warp::body::json())
+        .and_then(routes::vote_question);
+
+    let unvote_question = warp::delete()
+        .and(warp::path("questions"))
+        .and(warp::path::param::<Uuid>())
+        .and(warp::path("vote"))
+        .and(warp::path::end())
+        .and(routes::protect(config, &store))
+        .and(db_store.clone())
+        .and_then(routes::unvote_question);
+
+    let vote_answer = warp::put()
+        .and(warp::path("answers"))
+        .and(warp::path::param::<Uuid>())
+        .and(warp::path("vote"))
+        .and(warp::path::end())
+        .and(routes::protect(config, &store))
+        .and(db_store.clone())
+        .and(app_config.clone())
+        .and(warp::body::json())
+        .and_then(routes::vote_answer);
+
+    let unvote_answer = warp::delete()
+        .and(warp::path("answers"))
+        .and(warp::path::param::<Uuid>())
+        .and(warp::path("vote"))
+        .and(warp::path::end())
+        .and(routes::protect(config, &store))
+        .and(db_store.clone())
+        .and_then(routes::unvote_answer);
+
+    let accept_answer = warp::put()
+        .and(warp::path("answers"))
+        .and(warp::path::param::<Uuid>())
+        .and(warp::path("accept"))
+        .and(warp::path::end())
+        .and(routes::protect(config, &store))
+        .and(db_store.clone())
+        .and(app_config.clone())
+        .and_then(routes::accept_answer);
+
+    let unaccept_answer = warp::delete()
+        .and(warp::path("answers"))
+        .and(warp::path::param::<Uuid>())
+        .and(warp::path("accept"))
+        .and(warp::path::end())
+        .and(routes::protect(config, &store))
+        .and(db_store.clone())
+        .and_then(routes::unaccept_answer);
+
+    let get_privileges = warp::get()
+        .and(warp::path("me"))
+        .and(warp::path("privileges"))
+        .and(warp::path::end())
+        .and(routes::protect(config, &store))
+        .and(db_store.clone())
+        .and(app_config.clone())
+        .and_then(routes::get_privileges);
+
+    let get_user_profile = warp::get()
+        .and(warp::path("users"))
+        .and(warp::path::param::<Uuid>())
+        .and(warp::path::end())
+        .and(db_store.clone())
+        .and_then(routes::get_user_profile);
+
+    let get_reputation_events = warp::get()
+        .and(warp::path("users"))
+        .and(warp::path::param::<Uuid>())
+        .and(warp::path("reputation"))
+        .and(warp::path::end())
+        .and(db_store.clone())
+        .and(warp::query())
+        .and_then(routes::get_reputation_events);
+
+    let add_webhook = warp::post()
+        .and(warp::path("webhooks"))
+        .and(warp::path::end())
+        .and(routes::protect(config, &store))
+        .and(db_store.clone())
+        .and(warp::body::json())
+        .and_then(routes::add_webhook);
+
+    let get_webhooks = warp::get()
+        .and(warp::path("webhooks"))
+        .and(warp::path::end())
+        .and(routes::protect(config, &store))
+        .and(db_store.clone())
+        .and_then(routes::get_webhooks);
+
+    let delete_webhook = warp::delete()
+        .and(warp::path("webhooks"))
+        .and(warp::path::param::<Uuid>())
+        .and(warp::path::end())
+        .and(routes::protect(config, &store))
+        .and(db_store.clone())
+        .and_then(routes::delete_webhook);
+
+    let get_webhook_deliveries = warp::get()
+        .and(warp::path("webhooks"))
+        .and(warp::path::param::<Uuid>())
+        .and(warp::path("deliveries"))
+        .and(warp::path::end())
+        .and(routes::protect(config, &store))
+        .and(db_store.clone())
+        .and_then(routes::get_webhook_deliveries);
+
+    // Each group is boxed so that the combined filter, and the future it
+    // builds per request, stays shallow enough for debug builds' stacks.
+    let content = get_questions
         .or(get_question)
         .or(add_question)
         .or(update_question)
         .or(delete_question)
+        .or(add_close_vote)
+        .or(delete_close_vote)
+        .or(set_question_status)
+        .or(question_events_ws)
+        .or(question_events)
         .or(get_answers)
         .or(add_answer)
         .or(delete_answer)
-        .or(signup)
+        .or(preview)
+        .map(Reply::into_response)
+        .boxed();
+
+    let attachments = add_question_attachment
+        .or(add_answer_attachment)
+        .or(get_question_attachments)
+        .or(get_answer_attachments)
+        .or(get_attachment)
+        .map(Reply::into_response)
+        .boxed();
+
+    let users = signup
         .or(signin)
-        .recover(error::handle_rejection)
-        .with(warp::trace::request())
+        .or(get_notifications)
+        .or(mark_notifications_read)
+        .or(get_notification_preferences)
+        .or(set_notification_preferences)
+        .map(Reply::into_response)
+        .boxed();
+
+    let follows = follow_question
+        .or(unfollow_question)
+        .or(follow_tag)
+        .or(unfollow_tag)
+        .or(follow_user)
+        .or(unfollow_user)
+        .or(get_follows)
+        .or(get_feed)
+        .or(add_bookmark)
+        .or(delete_bookmark)
+        .or(get_bookmarks)
+        .map(Reply::into_response)
+        .boxed();
+
+    let reputation = vote_question
+        .or(unvote_question)
+        .or(vote_answer)
+        .or(unvote_answer)
+        .or(accept_answer)
+        .or(unaccept_answer)
+        .or(get_privileges)
+        .or(get_user_profile)
+        .or(get_reputation_events)
+        .map(Reply::into_response)
+        .boxed();
+
+    let webhooks = add_webhook
+        .or(get_webhooks)
+        .or(delete_webhook)
+        .or(get_webhook_deliveries)
+        .map(Reply::into_response)
+        .boxed();
+
+    content
+        .or(attachments)
+        .unify()
+        .or(users)
+        .unify()
+        .or(follows)
+        .unify()
+        .or(reputation)
+        .unify()
+        .or(webhooks)
+        .unify()
         .boxed()
 }
@@ -0,0 +1,93 @@
+//! Markdown to HTML for question and answer bodies.
+
+use std::sync::OnceLock;
+
+use ammonia::Builder;
+use pulldown_cmark::{html, Options, Parser};
+
+/// Renders CommonMark with GFM tables and strikethrough, then sanitizes the
+/// result so it is safe to insert into a page as is. Raw HTML in the source
+/// is kept only where the sanitizer allows it; scripts, event handlers and
+/// `javascript:` links are removed.
+pub fn render(markdown: &str) -> String {
+    let mut options = Options::empty();
+    options.insert(Options::ENABLE_TABLES);
+    options.insert(Options::ENABLE_STRIKETHROUGH);
+
+    let mut unsafe_html = String::with_capacity(markdown.len() * 3 / 2);
+    html::push_html(&mut unsafe_html, Parser::new_ext(markdown, options));
+
+    sanitizer().clean(&unsafe_html).to_string()
+}
+
+fn sanitizer() -> &'static Builder<'static> {
+    static SANITIZER: OnceLock<Builder<'static>> = OnceLock::new();
+
+    SANITIZER.get_or_init(|| {
+        let mut builder = Builder::default();
+        // Fenced code blocks carry their language as `language-*` for
+        // client-side highlighting; no other classes get through.
+        builder
+            .add_tag_attributes("code", &["class"])
+            .attribute_filter(|element, attribute, value| match (element, attribute) {
+                ("code", "class") if !value.starts_with("language-") => None,
+                _ => Some(value.into()),
+            });
+        builder
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::render;
+
+    #[test]
+    fn renders_commonmark_and_gfm() {
+        assert_eq!(
+            render("# Title\n\n**bold** ~~gone~~"),
+            "<h1>Title</h1>\n<p><strong>bold</strong> <del>gone</del></p>\n"
+        );
+        assert!(render("| a |\n|---|\n| 1 |").contains("<table>"));
+    }
+
+    #[test]
+    fn removes_scripts() {
+        let html = render("before\n\n<script>alert(1)</script>\n\nafter");
+
+        assert!(!html.contains("<script"));
+        assert!(!html.contains("alert(1)"));
+        assert!(html.contains("<p>after</p>"));
+    }
+
+    #[test]
+    fn removes_event_handlers() {
+        let html = render(r#"<img src="x.png" onerror="alert(1)">"#);
+
+        assert!(html.contains(r#"<img src="x.png">"#));
+        assert!(!html.contains("onerror"));
+    }
+
+    #[test]
+    fn removes_javascript_links() {
+        for markdown in [
+            "[click](javascript:alert(1))",
+            r#"<a href="javascript:alert(1)">click</a>"#,
+            r#"<a href="JaVaScRiPt:alert(1)">click</a>"#,
+        ] {
+            let html = render(markdown);
+            assert!(!html.to_lowercase().contains("javascript:"), "{}", html);
+            assert!(html.contains("click"), "{}", html);
+        }
+
+        assert!(render("[docs](https://example.com)").contains(r#"href="https://example.com""#));
+    }
+
+    #[test]
+    fn keeps_only_language_classes_on_code() {
+        assert!(render("```rust\nfn main() {}\n```").contains(r#"<code class="language-rust">"#));
+
+        let html = render(r#"<code class="evil">x</code> <span class="evil">y</span>"#);
+        assert!(html.contains("<code>x</code>"), "{}", html);
+        assert!(!html.contains("evil"), "{}", html);
+    }
+}
@@ -0,0 +1,222 @@
+use std::sync::Arc;
+
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGaugeVec, Opts, Registry,
+    TextEncoder,
+};
+use utoipa::OpenApi;
+use warp::log::Info;
+
+use crate::{
+    routes::{is_legacy, ApiDoc},
+    store::DbStore,
+};
+
+/// Routes served outside the OpenAPI document.
+const UNDOCUMENTED_ROUTES: &[&str] = &["/openapi.json", "/docs"];
+
+/// Process-wide Prometheus metrics, cheap to clone.
+#[derive(Debug, Clone)]
+pub struct Metrics {
+    registry: Registry,
+    routes: Arc<Vec<String>>,
+    requests: IntCounterVec,
+    latency: HistogramVec,
+    pool_connections: IntGaugeVec,
+    pub questions_created: IntCounter,
+    pub answers_created: IntCounter,
+    pub signins_failed: IntCounter,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        let registry = Registry::new_custom(Some("askly".to_string()), None).unwrap();
+
+        let requests = IntCounterVec::new(
+            Opts::new("http_requests_total", "HTTP requests by route and status"),
+            &["route", "method", "status"],
+        )
+        .unwrap();
+
+        let latency = HistogramVec::new(
+            HistogramOpts::new(
+                "http_request_duration_seconds",
+                "HTTP request latency by route",
+            ),
+            &["route", "method"],
+        )
+        .unwrap();
+
+        let pool_connections = IntGaugeVec::new(
+            Opts::new("db_pool_connections", "Database pool connections by state"),
+            &["state"],
+        )
+        .unwrap();
+
+        let questions_created =
+            IntCounter::new("questions_created_total", "Questions created").unwrap();
+        let answers_created = IntCounter::new("answers_created_total", "Answers created").unwrap();
+        let signins_failed = IntCounter::new("signins_failed_total", "Sign-ins rejected").unwrap();
+
+        registry.register(Box::new(requests.clone())).unwrap();
+        registry.register(Box::new(latency.clone())).unwrap();
+        registry
+            .register(Box::new(pool_connections.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(questions_created.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(answers_created.clone()))
+            .unwrap();
+        registry.register(Box::new(signins_failed.clone())).unwrap();
+
+        let mut routes: Vec<String> = ApiDoc::openapi().paths.paths.into_keys().collect();
+        routes.extend(UNDOCUMENTED_ROUTES.iter().map(|route| route.to_string()));
+        // Literal segments win over parameters, e.g. `/v1/me/feed` over a
+        // hypothetical `/v1/me/{id}`.
+        routes.sort_by_key(|route| route.matches('{').count());
+
+        Self {
+            registry,
+            routes: Arc::new(routes),
+            requests,
+            latency,
+            pool_connections,
+            questions_created,
+            answers_created,
+            signins_failed,
+        }
+    }
+
+    /// Records a finished request; used with `warp::log::custom`.
+    pub fn observe(&self, info: Info) {
+        let route = route_label(&self.routes, info.path());
+        let method = info.method().as_str();
+
+        self.requests
+            .with_label_values(&[&route, method, info.status().as_str()])
+            .inc();
+        self.latency
+            .with_label_values(&[&route, method])
+            .observe(info.elapsed().as_secs_f64());
+    }
+
+    /// Text exposition of every metric, sampling the pool at scrape time.
+    pub fn render(&self, store: &DbStore) -> String {
+        let size = store.conn.size() as i64;
+        let idle = store.conn.num_idle() as i64;
+        self.pool_connections.with_label_values(&["idle"]).set(idle);
+        self.pool_connections
+            .with_label_values(&["active"])
+            .set(size - idle);
+
+        let mut buf = Vec::new();
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buf)
+            .unwrap();
+
+        String::from_utf8(buf).unwrap()
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The template of the route that served `path`, e.g. `/v1/questions/{id}`,
+/// so the label set stays bounded whatever paths clients send. Root aliases
+/// are labeled with their template minus the `/v1` prefix; everything else,
+/// including paths rejected before routing, is `unmatched`.
+fn route_label(routes: &[String], path: &str) -> String {
+    let matching = |path: &str| {
+        routes
+            .iter()
+            .find(|route| matches_template(route, path))
+            .cloned()
+    };
+
+    let route = match matching(path) {
+        Some(route) => Some(route),
+        None if is_legacy(path.trim_start_matches('/')) => matching(&format!("/v1{}", path))
+            .map(|route| route.trim_start_matches("/v1").to_string()),
+        None => None,
+    };
+
+    route.unwrap_or_else(|| "unmatched".to_string())
+}
+
+fn matches_template(template: &str, path: &str) -> bool {
+    let mut segments = path.split('/');
+    let mut parts = template.split('/');
+
+    loop {
+        match (parts.next(), segments.next()) {
+            (None, None) => return true,
+            (Some(part), Some(segment)) if part.starts_with('{') => {
+                if segment.is_empty() {
+                    return false;
+                }
+            }
+            (Some(part), Some(segment)) if part == segment => {}
+            _ => return false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn label(path: &str) -> String {
+        route_label(&Metrics::new().routes, path)
+    }
+
+    #[test]
+    fn labels_requests_with_their_route_template() {
+        let id = "3f1c2a9e-8d6b-4f0e-9a7c-5b2d1e0f4a6c";
+
+        assert_eq!(label("/"), "/");
+        assert_eq!(label("/healthz"), "/healthz");
+        assert_eq!(label("/docs"), "/docs");
+        assert_eq!(label("/v1/questions"), "/v1/questions");
+        assert_eq!(
+            label(&format!("/v1/questions/{}", id)),
+            "/v1/questions/{id}"
+        );
+        assert_eq!(
+            label(&format!("/v1/questions/{}/answers", id)),
+            "/v1/questions/{question_id}/answers"
+        );
+        assert_eq!(
+            label("/v1/me/notifications/read"),
+            "/v1/me/notifications/read"
+        );
+    }
+
+    #[test]
+    fn path_parameters_share_one_label() {
+        assert_eq!(label("/v1/tags/rust/follow"), "/v1/tags/{tag}/follow");
+        assert_eq!(label("/v1/tags/sqlx/follow"), "/v1/tags/{tag}/follow");
+    }
+
+    #[test]
+    fn root_aliases_drop_the_version() {
+        let id = "3f1c2a9e-8d6b-4f0e-9a7c-5b2d1e0f4a6c";
+
+        assert_eq!(label(&format!("/questions/{}", id)), "/questions/{id}");
+        assert_eq!(label("/signin"), "/signin");
+        // Only the frozen set of root aliases exists.
+        assert_eq!(label("/tags/rust/follow"), "unmatched");
+    }
+
+    #[test]
+    fn unknown_paths_are_unmatched() {
+        assert_eq!(label("/wp-login.php"), "unmatched");
+        assert_eq!(label("/v1/questions/1/answers/extra"), "unmatched");
+        assert_eq!(label("/v1/tags//follow"), "unmatched");
+        assert_eq!(label("/healthz/"), "unmatched");
+    }
+}
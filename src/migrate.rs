@@ -0,0 +1,94 @@
+//! Schema migrations embedded from `migrations/`.
+//!
+//! Applying and reverting take a Postgres advisory lock (sqlx's migrator
+//! locks by default), so replicas started together run the migrations once
+//! and the others wait, then find nothing left to do.
+
+use sqlx::migrate::{Migrate, MigrateError};
+
+use crate::{
+    error::Error,
+    store::{DbStore, MIGRATOR},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MigrationState {
+    Applied,
+    Pending,
+    /// Applied, but the file changed since.
+    Modified,
+}
+
+#[derive(Debug, Clone)]
+pub struct MigrationStatus {
+    pub version: i64,
+    pub description: String,
+    pub state: MigrationState,
+}
+
+/// Applies every pending migration, then renders `content_html` for rows
+/// written before that column existed.
+pub async fn up(store: &DbStore) -> Result<(), MigrateError> {
+    MIGRATOR.run(&store.conn).await?;
+
+    match store.render_missing_html().await {
+        Ok(_) => Ok(()),
+        Err(Error::DbError(e)) => Err(MigrateError::Execute(e)),
+        Err(e) => Err(MigrateError::Source(Box::new(e))),
+    }
+}
+
+/// Reverts applied migrations newer than `to`, or just the latest one.
+/// Returns the version the schema is now at, `None` if nothing was reverted.
+pub async fn down(store: &DbStore, to: Option<i64>) -> Result<Option<i64>, MigrateError> {
+    let mut applied = applied_versions(store).await?;
+    applied.sort_unstable();
+
+    let target = match (to, applied.as_slice()) {
+        (_, []) => return Ok(None),
+        (Some(to), [.., latest]) if to >= *latest => return Ok(None),
+        (Some(to), _) => to,
+        (None, [.., previous, _]) => *previous,
+        (None, [_]) => 0,
+    };
+
+    MIGRATOR.undo(&store.conn, target).await?;
+    Ok(Some(target))
+}
+
+/// Every embedded migration and whether it is applied.
+pub async fn status(store: &DbStore) -> Result<Vec<MigrationStatus>, MigrateError> {
+    let mut conn = store.conn.acquire().await?;
+    conn.ensure_migrations_table().await?;
+    let applied = conn.list_applied_migrations().await?;
+
+    Ok(MIGRATOR
+        .iter()
+        .filter(|m| m.migration_type.is_up_migration())
+        .map(|m| {
+            let state = match applied.iter().find(|a| a.version == m.version) {
+                Some(a) if a.checksum == m.checksum => MigrationState::Applied,
+                Some(_) => MigrationState::Modified,
+                None => MigrationState::Pending,
+            };
+
+            MigrationStatus {
+                version: m.version,
+                description: m.description.to_string(),
+                state,
+            }
+        })
+        .collect())
+}
+
+async fn applied_versions(store: &DbStore) -> Result<Vec<i64>, MigrateError> {
+    let mut conn = store.conn.acquire().await?;
+    conn.ensure_migrations_table().await?;
+
+    Ok(conn
+        .list_applied_migrations()
+        .await?
+        .into_iter()
+        .map(|m| m.version)
+        .collect())
+}
@@ -1,3 +1,6 @@
+use std::sync::Arc;
+
+use tracing::instrument;
 use uuid::Uuid;
 use warp::{reject::Rejection, reply::Reply};
 
@@ -6,10 +9,23 @@ use crate::{
         answer::{Answer, NewAnswer},
         user::AuthPayload,
     },
-    error::Error,
+    markdown,
+    metrics::Metrics,
+    storage::{delete_bodies, Storage},
     store::DbStore,
 };
 
+use super::etag::check_if_match;
+
+#[utoipa::path(
+    get,
+    context_path = "/v1",
+    path = "/questions/{question_id}/answers",
+    tag = "answers",
+    params(("question_id" = Uuid, Path, description = "Question id")),
+    responses((status = 200, description = "Answers to the question", body = [AnswerView]))
+)]
+#[instrument(skip_all, fields(question_id = %question_id))]
 pub async fn get_answers(question_id: Uuid, store: DbStore) -> Result<impl Reply, Rejection> {
     match store.get_answers(question_id).await {
         Ok(answers) => Ok(warp::reply::json(&answers)),
@@ -17,35 +33,80 @@ pub async fn get_answers(question_id: Uuid, store: DbStore) -> Result<impl Reply
     }
 }
 
+#[utoipa::path(
+    post,
+    context_path = "/v1",
+    path = "/questions/{question_id}/answers",
+    tag = "answers",
+    params(("question_id" = Uuid, Path, description = "Question id")),
+    request_body = NewAnswer,
+    security(("token" = [])),
+    responses(
+        (status = 200, description = "The created answer", body = Answer),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody),
+        (status = 404, description = "No such question", body = ErrorBody),
+        (status = 409, description = "The question is closed or locked", body = ErrorBody),
+    )
+)]
+#[instrument(skip_all, fields(question_id = %question_id))]
 pub async fn add_answer(
     question_id: Uuid,
     auth: AuthPayload,
     store: DbStore,
+    metrics: Metrics,
     input: NewAnswer,
 ) -> Result<impl Reply, Rejection> {
     let answer = Answer {
         id: Uuid::new_v4(),
+        content_html: markdown::render(&input.content),
         content: input.content,
         question_id,
+        version: 1,
     };
 
     match store.add_answer(answer, auth.user_id).await {
-        Ok(answer) => Ok(warp::reply::json(&answer)),
+        Ok(answer) => {
+            metrics.answers_created.inc();
+            Ok(warp::reply::json(&answer))
+        }
         Err(e) => Err(warp::reject::custom(e)),
     }
 }
 
+#[utoipa::path(
+    delete,
+    context_path = "/v1",
+    path = "/answers/{id}",
+    tag = "answers",
+    params(
+        ("id" = Uuid, Path, description = "Answer id"),
+        ("If-Match" = String, Header, description = "ETag the deletion is based on"),
+    ),
+    security(("token" = [])),
+    responses(
+        (status = 200, description = "Deleted", body = bool, content_type = "application/json"),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody),
+        (status = 403, description = "Not the author", body = ErrorBody),
+        (status = 404, description = "No such answer", body = ErrorBody),
+        (status = 412, description = "The answer was modified since", body = ErrorBody),
+        (status = 428, description = "If-Match is missing", body = ErrorBody),
+    )
+)]
+#[instrument(skip_all, fields(answer_id = %id))]
 pub async fn delete_answer(
     id: Uuid,
     auth: AuthPayload,
+    if_match: Option<String>,
     store: DbStore,
+    storage: Arc<dyn Storage>,
 ) -> Result<impl Reply, Rejection> {
-    if !store.is_answer_owner(id, auth.user_id).await? {
-        return Err(warp::reject::custom(Error::NotOwner));
-    }
+    let mut tx = store.begin().await?;
+    let version = tx.lock_answer(id, auth.user_id).await?;
+    check_if_match(if_match, version)?;
+    let attachments = tx.delete_answer(id).await?;
+    tx.commit().await?;
 
-    match store.delete_answer(id).await {
-        Ok(_) => Ok(warp::reply::json(&true)),
-        Err(e) => Err(warp::reject::custom(e)),
-    }
+    delete_bodies(storage.as_ref(), attachments).await;
+
+    Ok(warp::reply::json(&true))
 }
@@ -0,0 +1,369 @@
+use std::sync::Arc;
+
+use bytes::BufMut;
+use chrono::Utc;
+use futures_util::TryStreamExt;
+use tracing::instrument;
+use utoipa::ToSchema;
+use uuid::Uuid;
+use warp::{
+    http::{header, Response},
+    hyper::Body,
+    multipart::FormData,
+    reject::Rejection,
+    reply::Reply,
+};
+
+use crate::{
+    config::Config,
+    domain::{
+        attachment::{Attachment, AttachmentTarget},
+        user::AuthPayload,
+    },
+    error::Error,
+    storage::{delete_bodies, Storage},
+    store::DbStore,
+};
+
+/// Multipart body of an upload; only describes the request in the OpenAPI
+/// document.
+#[allow(dead_code)]
+#[derive(ToSchema)]
+pub struct AttachmentUpload {
+    #[schema(value_type = String, format = Binary)]
+    file: Vec<u8>,
+}
+
+struct Upload {
+    filename: String,
+    content_type: String,
+    data: Vec<u8>,
+}
+
+#[utoipa::path(
+    post,
+    context_path = "/v1",
+    path = "/questions/{id}/attachments",
+    tag = "attachments",
+    params(("id" = Uuid, Path, description = "Question id")),
+    request_body(content = AttachmentUpload, content_type = "multipart/form-data"),
+    security(("token" = [])),
+    responses(
+        (status = 200, description = "The stored attachment", body = Attachment),
+        (status = 400, description = "No `file` part", body = ErrorBody),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody),
+        (status = 403, description = "Not the author", body = ErrorBody),
+        (status = 404, description = "No such question", body = ErrorBody),
+        (status = 413, description = "Larger than ATTACHMENT_MAX_BYTES", body = ErrorBody),
+        (status = 415, description = "Type not in ATTACHMENT_TYPES", body = ErrorBody),
+    )
+)]
+#[instrument(skip_all, fields(question_id = %id))]
+pub async fn add_question_attachment(
+    id: Uuid,
+    auth: AuthPayload,
+    store: DbStore,
+    storage: Arc<dyn Storage>,
+    config: Config,
+    form: FormData,
+) -> Result<impl Reply, Rejection> {
+    let attachment = upload(
+        AttachmentTarget::Question(id),
+        auth,
+        store,
+        storage,
+        config,
+        form,
+    )
+    .await?;
+
+    Ok(warp::reply::json(&attachment))
+}
+
+#[utoipa::path(
+    post,
+    context_path = "/v1",
+    path = "/answers/{id}/attachments",
+    tag = "attachments",
+    params(("id" = Uuid, Path, description = "Answer id")),
+    request_body(content = AttachmentUpload, content_type = "multipart/form-data"),
+    security(("token" = [])),
+    responses(
+        (status = 200, description = "The stored attachment", body = Attachment),
+        (status = 400, description = "No `file` part", body = ErrorBody),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody),
+        (status = 403, description = "Not the author", body = ErrorBody),
+        (status = 404, description = "No such answer", body = ErrorBody),
+        (status = 413, description = "Larger than ATTACHMENT_MAX_BYTES", body = ErrorBody),
+        (status = 415, description = "Type not in ATTACHMENT_TYPES", body = ErrorBody),
+    )
+)]
+#[instrument(skip_all, fields(answer_id = %id))]
+pub async fn add_answer_attachment(
+    id: Uuid,
+    auth: AuthPayload,
+    store: DbStore,
+    storage: Arc<dyn Storage>,
+    config: Config,
+    form: FormData,
+) -> Result<impl Reply, Rejection> {
+    let attachment = upload(
+        AttachmentTarget::Answer(id),
+        auth,
+        store,
+        storage,
+        config,
+        form,
+    )
+    .await?;
+
+    Ok(warp::reply::json(&attachment))
+}
+
+#[utoipa::path(
+    get,
+    context_path = "/v1",
+    path = "/questions/{id}/attachments",
+    tag = "attachments",
+    params(("id" = Uuid, Path, description = "Question id")),
+    security(("token" = [])),
+    responses(
+        (status = 200, description = "Attachments of the question", body = [Attachment]),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody),
+    )
+)]
+#[instrument(skip_all, fields(question_id = %id))]
+pub async fn get_question_attachments(
+    id: Uuid,
+    _auth: AuthPayload,
+    store: DbStore,
+) -> Result<impl Reply, Rejection> {
+    let attachments = store
+        .get_attachments(AttachmentTarget::Question(id))
+        .await?;
+    Ok(warp::reply::json(&attachments))
+}
+
+#[utoipa::path(
+    get,
+    context_path = "/v1",
+    path = "/answers/{id}/attachments",
+    tag = "attachments",
+    params(("id" = Uuid, Path, description = "Answer id")),
+    security(("token" = [])),
+    responses(
+        (status = 200, description = "Attachments of the answer", body = [Attachment]),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody),
+    )
+)]
+#[instrument(skip_all, fields(answer_id = %id))]
+pub async fn get_answer_attachments(
+    id: Uuid,
+    _auth: AuthPayload,
+    store: DbStore,
+) -> Result<impl Reply, Rejection> {
+    let attachments = store.get_attachments(AttachmentTarget::Answer(id)).await?;
+    Ok(warp::reply::json(&attachments))
+}
+
+#[utoipa::path(
+    get,
+    context_path = "/v1",
+    path = "/attachments/{id}",
+    tag = "attachments",
+    params(("id" = Uuid, Path, description = "Attachment id")),
+    security(("token" = [])),
+    responses(
+        (status = 200, description = "The file, served with its stored media type"),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody),
+        (status = 404, description = "No such attachment", body = ErrorBody),
+    )
+)]
+#[instrument(skip_all, fields(attachment_id = %id))]
+pub async fn get_attachment(
+    id: Uuid,
+    _auth: AuthPayload,
+    store: DbStore,
+    storage: Arc<dyn Storage>,
+) -> Result<impl Reply, Rejection> {
+    let attachment = store.get_attachment(id).await?;
+    let data = storage.get(&id.to_string()).await?;
+
+    // Images may be shown in place; everything else downloads. Either way the
+    // browser must not sniff a different type or run anything it contains.
+    let disposition = match attachment.content_type.starts_with("image/") {
+        true => "inline",
+        false => "attachment",
+    };
+
+    let response = Response::builder()
+        .header(header::CONTENT_TYPE, &attachment.content_type)
+        .header(
+            header::CONTENT_DISPOSITION,
+            format!("{}; filename=\"{}\"", disposition, attachment.filename),
+        )
+        .header(header::X_CONTENT_TYPE_OPTIONS, "nosniff")
+        .header(
+            header::CONTENT_SECURITY_POLICY,
+            "default-src 'none'; sandbox",
+        )
+        .body(Body::from(data));
+
+    match response {
+        Ok(response) => Ok(response),
+        Err(_) => Err(warp::reject::custom(Error::ServerError)),
+    }
+}
+
+async fn upload(
+    target: AttachmentTarget,
+    auth: AuthPayload,
+    store: DbStore,
+    storage: Arc<dyn Storage>,
+    config: Config,
+    form: FormData,
+) -> Result<Attachment, Error> {
+    let file = read_file(form, &config).await?;
+
+    let mut tx = store.begin().await?;
+    let (question_id, answer_id) = match target {
+        AttachmentTarget::Question(id) => {
+            tx.lock_question(id, auth.user_id).await?;
+            (Some(id), None)
+        }
+        AttachmentTarget::Answer(id) => {
+            tx.lock_answer(id, auth.user_id).await?;
+            (None, Some(id))
+        }
+    };
+
+    let attachment = Attachment {
+        id: Uuid::new_v4(),
+        question_id,
+        answer_id,
+        filename: file.filename,
+        size: file.data.len() as i64,
+        content_type: file.content_type,
+        created_on: Utc::now().naive_utc(),
+    };
+
+    let key = attachment.id.to_string();
+    storage
+        .put(&key, &attachment.content_type, file.data.into())
+        .await?;
+
+    let saved = async {
+        let attachment = tx.add_attachment(&attachment, auth.user_id).await?;
+        tx.commit().await?;
+        Ok(attachment)
+    }
+    .await;
+
+    if saved.is_err() {
+        delete_bodies(storage.as_ref(), vec![attachment.id]).await;
+    }
+
+    saved
+}
+
+/// Reads the `file` part, checking its declared type and size against the
+/// configured limits.
+async fn read_file(mut form: FormData, config: &Config) -> Result<Upload, Error> {
+    while let Some(part) = form.try_next().await.map_err(|_| Error::InvalidUpload)? {
+        if part.name() != "file" {
+            continue;
+        }
+
+        let content_type = part
+            .content_type()
+            .and_then(|t| t.split(';').next())
+            .map(|t| t.trim().to_ascii_lowercase())
+            .ok_or(Error::UnsupportedMediaType)?;
+        if !config.attachment_types.contains(&content_type) {
+            return Err(Error::UnsupportedMediaType);
+        }
+
+        let filename = sanitize_filename(part.filename().unwrap_or_default());
+
+        let max = config.attachment_max_bytes as usize;
+        let data = part
+            .stream()
+            .map_err(|_| Error::InvalidUpload)
+            .try_fold(Vec::new(), |mut data, chunk| async move {
+                if data.len() + bytes::Buf::remaining(&chunk) > max {
+                    return Err(Error::PayloadTooLarge);
+                }
+                data.put(chunk);
+                Ok(data)
+            })
+            .await?;
+
+        if data.is_empty() {
+            return Err(Error::InvalidUpload);
+        }
+
+        return Ok(Upload {
+            filename,
+            content_type,
+            data,
+        });
+    }
+
+    Err(Error::InvalidUpload)
+}
+
+/// Keeps the base name, limited to characters that are safe in a quoted
+/// `Content-Disposition` value.
+fn sanitize_filename(name: &str) -> String {
+    let base = name.rsplit(['/', '\\']).next().unwrap_or_default();
+    let clean: String = base
+        .chars()
+        .map(|c| match c {
+            '"' => '_',
+            c if c.is_ascii_graphic() || c == ' ' => c,
+            _ => '_',
+        })
+        .take(255)
+        .collect();
+
+    match clean.trim() {
+        "" | "." | ".." => "attachment".to_string(),
+        name => name.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::sanitize_filename;
+
+    #[test]
+    fn keeps_plain_names() {
+        assert_eq!(sanitize_filename("report final.pdf"), "report final.pdf");
+    }
+
+    #[test]
+    fn strips_directories() {
+        assert_eq!(sanitize_filename("../../etc/passwd"), "passwd");
+        assert_eq!(sanitize_filename(r"C:\Users\ada\photo.png"), "photo.png");
+    }
+
+    #[test]
+    fn replaces_unsafe_characters() {
+        assert_eq!(sanitize_filename("say \"hi\".txt"), "say _hi_.txt");
+        assert_eq!(sanitize_filename("line\r\nbreak.txt"), "line__break.txt");
+        assert_eq!(sanitize_filename("café.txt"), "caf_.txt");
+    }
+
+    #[test]
+    fn falls_back_for_empty_names() {
+        assert_eq!(sanitize_filename(""), "attachment");
+        assert_eq!(sanitize_filename("  "), "attachment");
+        assert_eq!(sanitize_filename("dir/"), "attachment");
+        assert_eq!(sanitize_filename(".."), "attachment");
+    }
+
+    #[test]
+    fn limits_the_length() {
+        assert_eq!(sanitize_filename(&"a".repeat(300)).len(), 255);
+    }
+}
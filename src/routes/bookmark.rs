@@ -0,0 +1,115 @@
+use tracing::instrument;
+use uuid::Uuid;
+use warp::{reject::Rejection, reply::Reply};
+
+use crate::{
+    domain::{
+        page::{Cursor, Page, PageQuery},
+        question::{Bookmark, Question},
+        user::AuthPayload,
+    },
+    error::Error,
+    store::DbStore,
+};
+
+use super::follow::page_cursor;
+
+/// Sets `bookmarked` on each question for a signed-in caller; anonymous
+/// callers get no flag at all.
+pub(super) async fn mark_bookmarked(
+    store: &DbStore,
+    auth: Option<&AuthPayload>,
+    questions: &mut [Question],
+) -> Result<(), Error> {
+    let Some(auth) = auth else {
+        return Ok(());
+    };
+
+    let ids: Vec<Uuid> = questions.iter().map(|q| q.id).collect();
+    let bookmarked = store.get_bookmarked(auth.user_id, &ids).await?;
+
+    for question in questions {
+        question.bookmarked = Some(bookmarked.contains(&question.id));
+    }
+
+    Ok(())
+}
+
+#[utoipa::path(
+    put,
+    context_path = "/v1",
+    path = "/questions/{id}/bookmark",
+    tag = "bookmarks",
+    params(("id" = Uuid, Path, description = "Question id")),
+    security(("token" = [])),
+    responses(
+        (status = 200, description = "Bookmarked", body = bool, content_type = "application/json"),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody),
+        (status = 404, description = "No such question", body = ErrorBody),
+    )
+)]
+#[instrument(skip_all, fields(question_id = %id))]
+pub async fn add_bookmark(
+    id: Uuid,
+    auth: AuthPayload,
+    store: DbStore,
+) -> Result<impl Reply, Rejection> {
+    store.add_bookmark(auth.user_id, id).await?;
+    Ok(warp::reply::json(&true))
+}
+
+#[utoipa::path(
+    delete,
+    context_path = "/v1",
+    path = "/questions/{id}/bookmark",
+    tag = "bookmarks",
+    params(("id" = Uuid, Path, description = "Question id")),
+    security(("token" = [])),
+    responses(
+        (status = 200, description = "Not bookmarked", body = bool, content_type = "application/json"),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody),
+    )
+)]
+#[instrument(skip_all, fields(question_id = %id))]
+pub async fn delete_bookmark(
+    id: Uuid,
+    auth: AuthPayload,
+    store: DbStore,
+) -> Result<impl Reply, Rejection> {
+    store.delete_bookmark(auth.user_id, id).await?;
+    Ok(warp::reply::json(&true))
+}
+
+#[utoipa::path(
+    get,
+    context_path = "/v1",
+    path = "/me/bookmarks",
+    tag = "bookmarks",
+    params(
+        ("limit" = Option<i64>, Query, description = "At most 100, default 20"),
+        ("before" = Option<String>, Query, description = "`next` of the previous page"),
+    ),
+    security(("token" = [])),
+    responses(
+        (status = 200, description = "Bookmarked questions, most recently bookmarked first", body = BookmarkPage),
+        (status = 400, description = "Malformed cursor", body = ErrorBody),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody),
+    )
+)]
+#[instrument(skip_all)]
+pub async fn get_bookmarks(
+    auth: AuthPayload,
+    store: DbStore,
+    query: PageQuery,
+) -> Result<impl Reply, Rejection> {
+    let cursor = page_cursor(&query)?;
+    let limit = query.limit();
+
+    let bookmarks = store.get_bookmarks(auth.user_id, cursor, limit + 1).await?;
+    let page = Page::new(bookmarks, limit, |bookmark: &Bookmark| Cursor {
+        created_on: bookmark.bookmarked_on,
+        id: bookmark.question.id,
+    });
+
+    Ok(warp::reply::json(&page))
+}
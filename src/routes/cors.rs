@@ -0,0 +1,28 @@
+use std::time::Duration;
+
+use warp::cors::Builder;
+
+use crate::config::Config;
+
+/// CORS policy for browser clients, `None` when no origins are configured.
+///
+/// Must wrap the recovered filter chain so that error responses carry the
+/// CORS headers too and preflight requests never reach the route filters.
+pub fn cors(config: &Config) -> Option<Builder> {
+    if config.cors_origins.is_empty() {
+        return None;
+    }
+
+    let cors = warp::cors()
+        .allow_methods(config.cors_methods.clone())
+        .allow_headers(config.cors_headers.clone())
+        .expose_headers(vec!["etag"])
+        .allow_credentials(config.cors_allow_credentials)
+        .max_age(Duration::from_secs(config.cors_max_age_secs));
+
+    if config.cors_origins.iter().any(|o| o == "*") {
+        Some(cors.allow_any_origin())
+    } else {
+        Some(cors.allow_origins(config.cors_origins.iter().map(String::as_str)))
+    }
+}
@@ -0,0 +1,90 @@
+use warp::{reject::Rejection, Filter};
+
+use crate::error::Error;
+
+/// Entity tag for a row version, e.g. `"3"`.
+pub fn etag(version: i32) -> String {
+    format!("\"{}\"", version)
+}
+
+/// Entity tag for a representation that also depends on the caller, e.g.
+/// `"3+bookmarked"`. Conditional updates only look at the version before
+/// the `+`.
+pub fn caller_etag(version: i32, variant: &str) -> String {
+    format!("\"{}+{}\"", version, variant)
+}
+
+fn tags(header: &str) -> impl Iterator<Item = &str> {
+    header.split(',').map(str::trim)
+}
+
+/// The version an opaque (strong) tag was issued for.
+fn tag_version(tag: &str) -> Option<&str> {
+    let tag = tag.strip_prefix('"')?.strip_suffix('"')?;
+    Some(tag.split_once('+').map_or(tag, |(version, _)| version))
+}
+
+pub fn if_match() -> impl Filter<Extract = (Option<String>,), Error = Rejection> + Clone {
+    warp::header::optional::<String>("if-match")
+}
+
+pub fn if_none_match() -> impl Filter<Extract = (Option<String>,), Error = Rejection> + Clone {
+    warp::header::optional::<String>("if-none-match")
+}
+
+/// Updates and deletes must name the version they were based on. Uses strong
+/// comparison, so weak tags never match.
+pub fn check_if_match(header: Option<String>, version: i32) -> Result<(), Error> {
+    let version = version.to_string();
+
+    match header {
+        None => Err(Error::PreconditionRequired),
+        Some(h) if tags(&h).any(|t| t == "*" || tag_version(t) == Some(&version)) => Ok(()),
+        Some(_) => Err(Error::PreconditionFailed),
+    }
+}
+
+/// Whether `If-None-Match` names exactly `tag`, compared weakly.
+pub fn is_not_modified(header: Option<String>, tag: &str) -> bool {
+    header.is_some_and(|h| tags(&h).any(|t| t == "*" || t.trim_start_matches("W/") == tag))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn if_match_compares_versions() {
+        assert!(check_if_match(Some(etag(3)), 3).is_ok());
+        assert!(check_if_match(Some(caller_etag(3, "bookmarked")), 3).is_ok());
+        assert!(check_if_match(Some("\"2\", \"3\"".to_string()), 3).is_ok());
+        assert!(check_if_match(Some("*".to_string()), 3).is_ok());
+
+        assert!(matches!(
+            check_if_match(Some(etag(2)), 3),
+            Err(Error::PreconditionFailed)
+        ));
+        assert!(matches!(
+            check_if_match(Some("W/\"3\"".to_string()), 3),
+            Err(Error::PreconditionFailed)
+        ));
+        assert!(matches!(
+            check_if_match(None, 3),
+            Err(Error::PreconditionRequired)
+        ));
+    }
+
+    #[test]
+    fn if_none_match_compares_whole_tags() {
+        let tag = caller_etag(3, "bookmarked");
+
+        assert!(is_not_modified(Some(tag.clone()), &tag));
+        assert!(is_not_modified(Some(format!("W/{}", tag)), &tag));
+        assert!(!is_not_modified(Some(etag(3)), &tag));
+        assert!(!is_not_modified(
+            Some(caller_etag(3, "not-bookmarked")),
+            &tag
+        ));
+        assert!(!is_not_modified(None, &tag));
+    }
+}
@@ -0,0 +1,104 @@
+use std::convert::Infallible;
+
+use futures_util::{stream, SinkExt, Stream, StreamExt};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::{debug, instrument, warn};
+use uuid::Uuid;
+use warp::{
+    filters::ws::{Message, WebSocket, Ws},
+    reject::Rejection,
+    reply::Reply,
+    sse,
+};
+
+use crate::{domain::event::Event, events::EventBus, store::DbStore};
+
+#[utoipa::path(
+    get,
+    context_path = "/v1",
+    path = "/questions/{id}/events",
+    tag = "questions",
+    params(("id" = Uuid, Path, description = "Question id")),
+    responses(
+        (status = 200, description = "Server-sent events named after their `type`; \
+            send `Upgrade: websocket` instead to receive the same events as JSON text \
+            messages", body = Event, content_type = "text/event-stream"),
+        (status = 404, description = "No such question", body = ErrorBody),
+    )
+)]
+#[instrument(skip_all, fields(question_id = %id))]
+pub async fn question_events(
+    id: Uuid,
+    store: DbStore,
+    events: EventBus,
+) -> Result<impl Reply, Rejection> {
+    store.get_question(id).await?;
+
+    let stream = question_stream(events.subscribe(), id).map(|event| {
+        let sse = sse::Event::default().event(event.name());
+        Ok::<_, Infallible>(sse.json_data(&event).unwrap_or_default())
+    });
+
+    Ok(sse::reply(sse::keep_alive().stream(stream)))
+}
+
+/// WebSocket variant of [`question_events`], served on the same path.
+#[instrument(skip_all, fields(question_id = %id))]
+pub async fn question_events_ws(
+    id: Uuid,
+    ws: Ws,
+    store: DbStore,
+    events: EventBus,
+) -> Result<impl Reply, Rejection> {
+    store.get_question(id).await?;
+
+    let receiver = events.subscribe();
+    Ok(ws.on_upgrade(move |socket| forward_to_socket(socket, receiver, id)))
+}
+
+/// Sends the question's events until the client goes away. Anything the
+/// client sends is ignored.
+async fn forward_to_socket(socket: WebSocket, receiver: broadcast::Receiver<Event>, id: Uuid) {
+    let (mut tx, mut rx) = socket.split();
+    let mut events = Box::pin(question_stream(receiver, id));
+
+    loop {
+        tokio::select! {
+            event = events.next() => {
+                let Some(event) = event else { break };
+                let text = match serde_json::to_string(&event) {
+                    Ok(text) => text,
+                    Err(_) => continue,
+                };
+                if tx.send(Message::text(text)).await.is_err() {
+                    break;
+                }
+            }
+            message = rx.next() => match message {
+                Some(Ok(message)) if !message.is_close() => {}
+                _ => break,
+            },
+        }
+    }
+
+    tx.close().await.ok();
+    debug!("event socket closed");
+}
+
+/// Events for one question. A subscriber that falls too far behind skips
+/// what it missed rather than closing.
+fn question_stream(receiver: broadcast::Receiver<Event>, id: Uuid) -> impl Stream<Item = Event> {
+    stream::unfold(receiver, move |mut receiver| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(event) if event.question_id() == id => return Some((event, receiver)),
+                Ok(_) => continue,
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!("event subscriber lagged, skipped {} events", skipped);
+                    continue;
+                }
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    })
+}
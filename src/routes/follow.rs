@@ -0,0 +1,216 @@
+use tracing::instrument;
+use uuid::Uuid;
+use warp::{reject::Rejection, reply::Reply};
+
+use crate::{
+    domain::{
+        feed::FeedItem,
+        page::{Cursor, Page, PageQuery},
+        user::AuthPayload,
+    },
+    error::Error,
+    store::DbStore,
+};
+
+/// Parses `before`; the first page has none.
+pub(super) fn page_cursor(query: &PageQuery) -> Result<Option<Cursor>, Error> {
+    match query.before.as_deref() {
+        None | Some("") => Ok(None),
+        Some(before) => match Cursor::parse(before) {
+            Some(cursor) => Ok(Some(cursor)),
+            None => Err(Error::InvalidInput("malformed `before` cursor".to_string())),
+        },
+    }
+}
+
+#[utoipa::path(
+    put,
+    context_path = "/v1",
+    path = "/questions/{id}/follow",
+    tag = "follows",
+    params(("id" = Uuid, Path, description = "Question id")),
+    security(("token" = [])),
+    responses(
+        (status = 200, description = "Following; new answers show up in the feed", body = bool, content_type = "application/json"),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody),
+        (status = 404, description = "No such question", body = ErrorBody),
+    )
+)]
+#[instrument(skip_all, fields(question_id = %id))]
+pub async fn follow_question(
+    id: Uuid,
+    auth: AuthPayload,
+    store: DbStore,
+) -> Result<impl Reply, Rejection> {
+    store.follow_question(auth.user_id, id).await?;
+    Ok(warp::reply::json(&true))
+}
+
+#[utoipa::path(
+    delete,
+    context_path = "/v1",
+    path = "/questions/{id}/follow",
+    tag = "follows",
+    params(("id" = Uuid, Path, description = "Question id")),
+    security(("token" = [])),
+    responses(
+        (status = 200, description = "Not following", body = bool, content_type = "application/json"),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody),
+    )
+)]
+#[instrument(skip_all, fields(question_id = %id))]
+pub async fn unfollow_question(
+    id: Uuid,
+    auth: AuthPayload,
+    store: DbStore,
+) -> Result<impl Reply, Rejection> {
+    store.unfollow_question(auth.user_id, id).await?;
+    Ok(warp::reply::json(&true))
+}
+
+#[utoipa::path(
+    put,
+    context_path = "/v1",
+    path = "/tags/{tag}/follow",
+    tag = "follows",
+    params(("tag" = String, Path, description = "Tag")),
+    security(("token" = [])),
+    responses(
+        (status = 200, description = "Following; new questions with the tag show up in the feed", body = bool, content_type = "application/json"),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody),
+    )
+)]
+#[instrument(skip_all, fields(tag = %tag))]
+pub async fn follow_tag(
+    tag: String,
+    auth: AuthPayload,
+    store: DbStore,
+) -> Result<impl Reply, Rejection> {
+    store.follow_tag(auth.user_id, &tag).await?;
+    Ok(warp::reply::json(&true))
+}
+
+#[utoipa::path(
+    delete,
+    context_path = "/v1",
+    path = "/tags/{tag}/follow",
+    tag = "follows",
+    params(("tag" = String, Path, description = "Tag")),
+    security(("token" = [])),
+    responses(
+        (status = 200, description = "Not following", body = bool, content_type = "application/json"),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody),
+    )
+)]
+#[instrument(skip_all, fields(tag = %tag))]
+pub async fn unfollow_tag(
+    tag: String,
+    auth: AuthPayload,
+    store: DbStore,
+) -> Result<impl Reply, Rejection> {
+    store.unfollow_tag(auth.user_id, &tag).await?;
+    Ok(warp::reply::json(&true))
+}
+
+#[utoipa::path(
+    put,
+    context_path = "/v1",
+    path = "/users/{id}/follow",
+    tag = "follows",
+    params(("id" = Uuid, Path, description = "User id")),
+    security(("token" = [])),
+    responses(
+        (status = 200, description = "Following; the user's new questions and answers show up in the feed", body = bool, content_type = "application/json"),
+        (status = 400, description = "Following yourself", body = ErrorBody),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody),
+        (status = 404, description = "No such user", body = ErrorBody),
+    )
+)]
+#[instrument(skip_all, fields(followed_id = %id))]
+pub async fn follow_user(
+    id: Uuid,
+    auth: AuthPayload,
+    store: DbStore,
+) -> Result<impl Reply, Rejection> {
+    if id == auth.user_id {
+        return Err(warp::reject::custom(Error::InvalidInput(
+            "cannot follow yourself".to_string(),
+        )));
+    }
+
+    store.follow_user(auth.user_id, id).await?;
+    Ok(warp::reply::json(&true))
+}
+
+#[utoipa::path(
+    delete,
+    context_path = "/v1",
+    path = "/users/{id}/follow",
+    tag = "follows",
+    params(("id" = Uuid, Path, description = "User id")),
+    security(("token" = [])),
+    responses(
+        (status = 200, description = "Not following", body = bool, content_type = "application/json"),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody),
+    )
+)]
+#[instrument(skip_all, fields(followed_id = %id))]
+pub async fn unfollow_user(
+    id: Uuid,
+    auth: AuthPayload,
+    store: DbStore,
+) -> Result<impl Reply, Rejection> {
+    store.unfollow_user(auth.user_id, id).await?;
+    Ok(warp::reply::json(&true))
+}
+
+#[utoipa::path(
+    get,
+    context_path = "/v1",
+    path = "/me/follows",
+    tag = "follows",
+    security(("token" = [])),
+    responses(
+        (status = 200, description = "Followed questions, tags and users", body = Follows),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody),
+    )
+)]
+#[instrument(skip_all)]
+pub async fn get_follows(auth: AuthPayload, store: DbStore) -> Result<impl Reply, Rejection> {
+    let follows = store.get_follows(auth.user_id).await?;
+    Ok(warp::reply::json(&follows))
+}
+
+#[utoipa::path(
+    get,
+    context_path = "/v1",
+    path = "/me/feed",
+    tag = "follows",
+    params(
+        ("limit" = Option<i64>, Query, description = "At most 100, default 20"),
+        ("before" = Option<String>, Query, description = "`next` of the previous page"),
+    ),
+    security(("token" = [])),
+    responses(
+        (status = 200, description = "New questions and answers from followed questions, tags and users, newest first", body = FeedPage),
+        (status = 400, description = "Malformed cursor", body = ErrorBody),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody),
+    )
+)]
+#[instrument(skip_all)]
+pub async fn get_feed(
+    auth: AuthPayload,
+    store: DbStore,
+    query: PageQuery,
+) -> Result<impl Reply, Rejection> {
+    let cursor = page_cursor(&query)?;
+    let limit = query.limit();
+
+    let items = store.get_feed(auth.user_id, cursor, limit + 1).await?;
+    let page = Page::new(items, limit, |item: &FeedItem| Cursor {
+        created_on: item.created_on,
+        id: item.id,
+    });
+
+    Ok(warp::reply::json(&page))
+}
@@ -0,0 +1,92 @@
+use chrono::DateTime;
+use serde::Serialize;
+use utoipa::ToSchema;
+use warp::{http::StatusCode, reject::Rejection, reply::Reply};
+
+use crate::store::DbStore;
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Readiness {
+    ready: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reason: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BuildInfo {
+    version: &'static str,
+    git_sha: &'static str,
+    built_at: String,
+}
+
+#[utoipa::path(
+    get,
+    path = "/",
+    tag = "health",
+    responses((status = 200, description = "Greeting", body = String, content_type = "text/plain"))
+)]
+pub async fn hello() -> Result<impl Reply, Rejection> {
+    Ok("Hello, world!".to_string())
+}
+
+/// Liveness: the process is up and serving requests.
+#[utoipa::path(
+    get,
+    path = "/healthz",
+    tag = "health",
+    responses((status = 200, description = "The process is up", body = bool, content_type = "application/json"))
+)]
+pub async fn healthz() -> Result<impl Reply, Rejection> {
+    Ok(warp::reply::json(&true))
+}
+
+/// Readiness: the database answers and every embedded migration is applied.
+#[utoipa::path(
+    get,
+    path = "/readyz",
+    tag = "health",
+    responses(
+        (status = 200, description = "Ready to serve", body = Readiness),
+        (status = 503, description = "Database unavailable or migrations pending", body = Readiness),
+    )
+)]
+pub async fn readyz(store: DbStore) -> Result<impl Reply, Rejection> {
+    let reason = match store.pending_migrations().await {
+        Ok(pending) if pending.is_empty() => None,
+        Ok(pending) => Some(format!("pending migrations: {:?}", pending)),
+        Err(_) => Some("database unavailable".to_string()),
+    };
+
+    let status = match reason {
+        None => StatusCode::OK,
+        Some(_) => StatusCode::SERVICE_UNAVAILABLE,
+    };
+
+    let body = Readiness {
+        ready: reason.is_none(),
+        reason,
+    };
+
+    Ok(warp::reply::with_status(warp::reply::json(&body), status))
+}
+
+#[utoipa::path(
+    get,
+    path = "/version",
+    tag = "health",
+    responses((status = 200, description = "Build information", body = BuildInfo))
+)]
+pub async fn version() -> Result<impl Reply, Rejection> {
+    let built_at = env!("ASKLY_BUILT_AT")
+        .parse()
+        .ok()
+        .and_then(|secs| DateTime::from_timestamp(secs, 0))
+        .map(|t| t.to_rfc3339())
+        .unwrap_or_default();
+
+    Ok(warp::reply::json(&BuildInfo {
+        version: env!("CARGO_PKG_VERSION"),
+        git_sha: env!("ASKLY_GIT_SHA"),
+        built_at,
+    }))
+}
@@ -0,0 +1,52 @@
+use std::future;
+
+use warp::{
+    filters::path::FullPath, http::HeaderValue, reject::Rejection, reply::Response, Filter,
+};
+
+/// Top-level paths that were served at the root before `/v1` existed. The
+/// list is frozen: top-level paths added since are only reachable under a
+/// version.
+const LEGACY_PATHS: &[&str] = &["questions", "answers", "signup", "signin"];
+
+/// RFC 9745 structured date of 2026-10-19, when the root aliases were deprecated.
+const DEPRECATION: &str = "@1792368000";
+const SUNSET: &str = "Mon, 19 Apr 2027 00:00:00 GMT";
+
+/// Matches requests to the unversioned aliases of `/v1` routes, leaving the
+/// path untouched so the `/v1` tree can route them.
+pub fn legacy_alias() -> impl Filter<Extract = (), Error = Rejection> + Clone {
+    warp::path::peek()
+        .and_then(|peek: warp::path::Peek| {
+            if is_legacy(peek.as_str()) {
+                future::ready(Ok(()))
+            } else {
+                future::ready(Err(warp::reject::not_found()))
+            }
+        })
+        .untuple_one()
+}
+
+/// Adds `Deprecation`, `Sunset` and a successor `Link` to every response,
+/// errors included, served from a root alias.
+pub fn deprecate_legacy(path: FullPath, mut response: Response) -> Response {
+    let path = path.as_str();
+    if !is_legacy(path.trim_start_matches('/')) {
+        return response;
+    }
+
+    let headers = response.headers_mut();
+    headers.insert("deprecation", HeaderValue::from_static(DEPRECATION));
+    headers.insert("sunset", HeaderValue::from_static(SUNSET));
+    if let Ok(link) = HeaderValue::from_str(&format!("</v1{}>; rel=\"successor-version\"", path)) {
+        headers.insert("link", link);
+    }
+
+    response
+}
+
+/// Whether `path`, without its leading `/`, is served by a root alias.
+pub fn is_legacy(path: &str) -> bool {
+    let segment = path.split('/').next().unwrap_or_default();
+    LEGACY_PATHS.contains(&segment)
+}
@@ -0,0 +1,17 @@
+use warp::{reject::Rejection, reply::Reply};
+
+use crate::{metrics::Metrics, store::DbStore};
+
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    tag = "health",
+    responses((status = 200, description = "Prometheus text exposition", body = String, content_type = "text/plain"))
+)]
+pub async fn metrics(metrics: Metrics, store: DbStore) -> Result<impl Reply, Rejection> {
+    Ok(warp::reply::with_header(
+        metrics.render(&store),
+        "Content-Type",
+        "text/plain; version=0.0.4",
+    ))
+}
@@ -1,7 +1,43 @@
 mod answer;
+mod attachment;
+mod bookmark;
+mod cors;
+mod etag;
+mod event;
+mod follow;
+mod health;
+mod legacy;
+mod metrics;
+mod notification;
+mod openapi;
+mod preview;
+mod privilege;
 mod question;
+mod rate_limit;
+mod reputation;
+mod request_id;
+mod status;
 mod user;
+mod webhook;
 
 pub use answer::*;
+pub use attachment::*;
+pub use bookmark::*;
+pub use cors::*;
+pub use etag::*;
+pub use event::*;
+pub use follow::*;
+pub use health::*;
+pub use legacy::*;
+pub use metrics::*;
+pub use notification::*;
+pub use openapi::*;
+pub use preview::*;
+pub use privilege::*;
 pub use question::*;
+pub use rate_limit::*;
+pub use reputation::*;
+pub use request_id::*;
+pub use status::*;
 pub use user::*;
+pub use webhook::*;
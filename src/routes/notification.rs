@@ -0,0 +1,117 @@
+use tracing::instrument;
+use warp::{reject::Rejection, reply::Reply};
+
+use crate::{
+    domain::{
+        notification::{MarkRead, NotificationList, NotificationPreferences, NotificationQuery},
+        user::AuthPayload,
+    },
+    store::DbStore,
+};
+
+const DEFAULT_LIMIT: i64 = 50;
+const MAX_LIMIT: i64 = 100;
+
+#[utoipa::path(
+    get,
+    context_path = "/v1",
+    path = "/me/notifications",
+    tag = "notifications",
+    params(
+        ("unread" = Option<bool>, Query, description = "Only unread notifications"),
+        ("limit" = Option<i64>, Query, description = "At most 100, default 50"),
+    ),
+    security(("token" = [])),
+    responses(
+        (status = 200, description = "Newest first, with the total unread count", body = NotificationList),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody),
+    )
+)]
+#[instrument(skip_all)]
+pub async fn get_notifications(
+    auth: AuthPayload,
+    store: DbStore,
+    query: NotificationQuery,
+) -> Result<impl Reply, Rejection> {
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+
+    let notifications = store
+        .get_notifications(auth.user_id, query.unread, limit)
+        .await?;
+    let unread = store.count_unread_notifications(auth.user_id).await?;
+
+    Ok(warp::reply::json(&NotificationList {
+        unread,
+        notifications,
+    }))
+}
+
+#[utoipa::path(
+    post,
+    context_path = "/v1",
+    path = "/me/notifications/read",
+    tag = "notifications",
+    request_body = MarkRead,
+    security(("token" = [])),
+    responses(
+        (status = 200, description = "Unread count afterwards", body = i64, content_type = "application/json"),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody),
+    )
+)]
+#[instrument(skip_all)]
+pub async fn mark_notifications_read(
+    auth: AuthPayload,
+    store: DbStore,
+    input: MarkRead,
+) -> Result<impl Reply, Rejection> {
+    store
+        .mark_notifications_read(auth.user_id, input.ids.as_deref())
+        .await?;
+    let unread = store.count_unread_notifications(auth.user_id).await?;
+
+    Ok(warp::reply::json(&unread))
+}
+
+#[utoipa::path(
+    get,
+    context_path = "/v1",
+    path = "/me/notification-preferences",
+    tag = "notifications",
+    security(("token" = [])),
+    responses(
+        (status = 200, description = "Which events notify", body = NotificationPreferences),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody),
+    )
+)]
+#[instrument(skip_all)]
+pub async fn get_notification_preferences(
+    auth: AuthPayload,
+    store: DbStore,
+) -> Result<impl Reply, Rejection> {
+    let preferences = store.get_notification_preferences(auth.user_id).await?;
+    Ok(warp::reply::json(&preferences))
+}
+
+#[utoipa::path(
+    put,
+    context_path = "/v1",
+    path = "/me/notification-preferences",
+    tag = "notifications",
+    request_body = NotificationPreferences,
+    security(("token" = [])),
+    responses(
+        (status = 200, description = "The saved preferences", body = NotificationPreferences),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody),
+    )
+)]
+#[instrument(skip_all)]
+pub async fn set_notification_preferences(
+    auth: AuthPayload,
+    store: DbStore,
+    input: NotificationPreferences,
+) -> Result<impl Reply, Rejection> {
+    store
+        .set_notification_preferences(auth.user_id, &input)
+        .await?;
+    Ok(warp::reply::json(&input))
+}
@@ -0,0 +1,188 @@
+use utoipa::{
+    openapi::security::{ApiKey, ApiKeyValue, SecurityScheme},
+    Modify, OpenApi,
+};
+use warp::{reject::Rejection, reply::Reply};
+
+use crate::{
+    domain::{
+        answer::{Answer, AnswerView, NewAnswer},
+        attachment::Attachment,
+        event::Event,
+        feed::{FeedItem, FeedKind, Follows},
+        notification::{
+            MarkRead, Notification, NotificationKind, NotificationList, NotificationPreferences,
+        },
+        page::{BookmarkPage, FeedPage, ReputationPage},
+        question::{
+            Bookmark, CloseReason, CloseVotes, NewCloseVote, NewQuestion, Question, QuestionStatus,
+            StatusChange,
+        },
+        reputation::{
+            AuthorSummary, Badge, BadgeKind, NewVote, Privilege, PrivilegeStatus, Privileges,
+            ReputationEvent, ReputationReason, UserProfile, VoteResult,
+        },
+        user::{Credential, NewUser},
+        webhook::{DeliveryStatus, NewWebhook, Webhook, WebhookDelivery, WebhookEvent},
+    },
+    error::ErrorBody,
+};
+
+use super::{AttachmentUpload, BuildInfo, Preview, PreviewInput, Readiness};
+
+/// OpenAPI description of every route in `build_routes`.
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Askly", description = "Questions and answers API"),
+    paths(
+        super::hello,
+        super::healthz,
+        super::readyz,
+        super::version,
+        super::metrics,
+        super::get_questions,
+        super::get_question,
+        super::add_question,
+        super::update_question,
+        super::delete_question,
+        super::add_close_vote,
+        super::delete_close_vote,
+        super::set_question_status,
+        super::get_answers,
+        super::add_answer,
+        super::delete_answer,
+        super::signup,
+        super::signin,
+        super::preview,
+        super::add_question_attachment,
+        super::add_answer_attachment,
+        super::get_question_attachments,
+        super::get_answer_attachments,
+        super::get_attachment,
+        super::question_events,
+        super::get_notifications,
+        super::mark_notifications_read,
+        super::get_notification_preferences,
+        super::set_notification_preferences,
+        super::follow_question,
+        super::unfollow_question,
+        super::follow_tag,
+        super::unfollow_tag,
+        super::follow_user,
+        super::unfollow_user,
+        super::get_follows,
+        super::get_feed,
+        super::add_bookmark,
+        super::delete_bookmark,
+        super::get_bookmarks,
+        super::vote_question,
+        super::unvote_question,
+        super::vote_answer,
+        super::unvote_answer,
+        super::accept_answer,
+        super::unaccept_answer,
+        super::get_privileges,
+        super::get_user_profile,
+        super::get_reputation_events,
+        super::add_webhook,
+        super::get_webhooks,
+        super::delete_webhook,
+        super::get_webhook_deliveries,
+    ),
+    components(schemas(
+        Question,
+        NewQuestion,
+        QuestionStatus,
+        CloseReason,
+        NewCloseVote,
+        CloseVotes,
+        StatusChange,
+        Answer,
+        NewAnswer,
+        NewUser,
+        Credential,
+        ErrorBody,
+        Readiness,
+        BuildInfo,
+        PreviewInput,
+        Preview,
+        Attachment,
+        AttachmentUpload,
+        Event,
+        Notification,
+        NotificationKind,
+        NotificationList,
+        MarkRead,
+        NotificationPreferences,
+        FeedKind,
+        FeedItem,
+        FeedPage,
+        Follows,
+        Bookmark,
+        BookmarkPage,
+        AnswerView,
+        AuthorSummary,
+        NewVote,
+        VoteResult,
+        UserProfile,
+        Badge,
+        BadgeKind,
+        ReputationEvent,
+        ReputationReason,
+        ReputationPage,
+        Privilege,
+        PrivilegeStatus,
+        Privileges,
+        Webhook,
+        NewWebhook,
+        WebhookEvent,
+        WebhookDelivery,
+        DeliveryStatus,
+    )),
+    modifiers(&TokenAuth)
+)]
+pub struct ApiDoc;
+
+/// Tokens from `/signin` are sent as-is in `Authorization`, without a scheme.
+struct TokenAuth;
+
+impl Modify for TokenAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        if let Some(components) = openapi.components.as_mut() {
+            components.add_security_scheme(
+                "token",
+                SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new("Authorization"))),
+            );
+        }
+    }
+}
+
+pub async fn openapi_json() -> Result<impl Reply, Rejection> {
+    let mut doc = ApiDoc::openapi();
+    // The crate has no license; utoipa would emit an empty one.
+    doc.info.license = None;
+
+    Ok(warp::reply::json(&doc))
+}
+
+/// Swagger UI for `/openapi.json`, loaded from a CDN.
+pub async fn docs() -> Result<impl Reply, Rejection> {
+    Ok(warp::reply::html(DOCS_HTML))
+}
+
+const DOCS_HTML: &str = r##"<!doctype html>
+<html lang="en">
+<head>
+  <meta charset="utf-8">
+  <title>Askly API</title>
+  <link rel="stylesheet" href="https://unpkg.com/swagger-ui-dist@5/swagger-ui.css">
+</head>
+<body>
+  <div id="swagger-ui"></div>
+  <script src="https://unpkg.com/swagger-ui-dist@5/swagger-ui-bundle.js"></script>
+  <script>
+    window.ui = SwaggerUIBundle({ url: "/openapi.json", dom_id: "#swagger-ui" });
+  </script>
+</body>
+</html>
+"##;
@@ -0,0 +1,31 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use warp::{reject::Rejection, reply::Reply};
+
+use crate::markdown;
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct PreviewInput {
+    /// Markdown source.
+    content: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Preview {
+    content_html: String,
+}
+
+/// Renders Markdown exactly as a saved question or answer would be.
+#[utoipa::path(
+    post,
+    context_path = "/v1",
+    path = "/preview",
+    tag = "questions",
+    request_body = PreviewInput,
+    responses((status = 200, description = "Sanitized HTML", body = Preview))
+)]
+pub async fn preview(input: PreviewInput) -> Result<impl Reply, Rejection> {
+    Ok(warp::reply::json(&Preview {
+        content_html: markdown::render(&input.content),
+    }))
+}
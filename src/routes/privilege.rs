@@ -0,0 +1,120 @@
+use tracing::instrument;
+use uuid::Uuid;
+use warp::{reject::Rejection, reply::Reply, Filter};
+
+use crate::{
+    config::{Config, PrivilegeConfig},
+    domain::{
+        reputation::{Privilege, PrivilegeStatus, Privileges},
+        user::{AuthPayload, Role},
+    },
+    error::Error,
+    store::DbStore,
+};
+
+use super::user::protect;
+
+async fn is_admin(store: &DbStore, user_id: Uuid) -> Result<bool, Error> {
+    match store.get_user_role(user_id).await {
+        Ok(role) => Ok(role == Role::Admin),
+        Err(Error::NotFound) => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+/// Fails with [`Error::MissingPrivilege`] unless the user has the reputation
+/// the privilege needs. Admins have every privilege.
+pub(super) async fn require_privilege(
+    store: &DbStore,
+    thresholds: &PrivilegeConfig,
+    auth: &AuthPayload,
+    privilege: Privilege,
+) -> Result<(), Error> {
+    if is_admin(store, auth.user_id).await? {
+        return Ok(());
+    }
+
+    let required = thresholds.required(privilege);
+    let reputation = store.get_reputation(auth.user_id).await?;
+    if reputation < required {
+        return Err(Error::MissingPrivilege {
+            privilege,
+            required,
+            reputation,
+        });
+    }
+
+    Ok(())
+}
+
+/// Requires [`Privilege::CreateTags`] when any of `tags` is not in use yet.
+pub(super) async fn require_known_tags(
+    store: &DbStore,
+    thresholds: &PrivilegeConfig,
+    auth: &AuthPayload,
+    tags: Option<&[String]>,
+) -> Result<(), Error> {
+    match tags {
+        Some(tags) if !tags.is_empty() && !store.get_new_tags(tags).await?.is_empty() => {
+            require_privilege(store, thresholds, auth, Privilege::CreateTags).await
+        }
+        _ => Ok(()),
+    }
+}
+
+/// [`protect`] for routes that need a privilege: signed-in callers without
+/// it are rejected with [`Error::MissingPrivilege`].
+pub fn privileged(
+    privilege: Privilege,
+    config: &Config,
+    store: &DbStore,
+) -> impl Filter<Extract = (AuthPayload,), Error = warp::Rejection> + Clone {
+    let thresholds = config.privileges;
+    let store = store.clone();
+
+    protect(config, &store).and_then(move |auth: AuthPayload| {
+        let store = store.clone();
+        async move {
+            require_privilege(&store, &thresholds, &auth, privilege).await?;
+            Ok::<_, Rejection>(auth)
+        }
+    })
+}
+
+#[utoipa::path(
+    get,
+    context_path = "/v1",
+    path = "/me/privileges",
+    tag = "reputation",
+    security(("token" = [])),
+    responses(
+        (status = 200, description = "Your reputation and which privileges it grants", body = Privileges),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody),
+    )
+)]
+#[instrument(skip_all)]
+pub async fn get_privileges(
+    auth: AuthPayload,
+    store: DbStore,
+    config: Config,
+) -> Result<impl Reply, Rejection> {
+    let reputation = store.get_reputation(auth.user_id).await?;
+    let admin = is_admin(&store, auth.user_id).await?;
+
+    let privileges = Privilege::ALL
+        .into_iter()
+        .map(|privilege| {
+            let required = config.privileges.required(privilege);
+            PrivilegeStatus {
+                privilege,
+                required,
+                granted: admin || reputation >= required,
+            }
+        })
+        .collect();
+
+    Ok(warp::reply::json(&Privileges {
+        reputation,
+        privileges,
+    }))
+}
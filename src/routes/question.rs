@@ -1,81 +1,256 @@
+use std::sync::Arc;
+
+use tracing::instrument;
 use uuid::Uuid;
-use warp::{reject::Rejection, reply::Reply};
+use warp::{http::StatusCode, reject::Rejection, reply::Reply};
 
 use crate::{
+    config::Config,
     domain::{
-        question::{NewQuestion, Question},
+        question::{NewQuestion, Question, QuestionStatus},
+        reputation::Privilege,
         user::AuthPayload,
     },
     error::Error,
+    markdown,
+    metrics::Metrics,
+    storage::{delete_bodies, Storage},
     store::DbStore,
 };
 
-pub async fn get_questions(store: DbStore) -> Result<impl Reply, Rejection> {
-    match store.get_questions().await {
-        Ok(questions) => Ok(warp::reply::json(&questions)),
-        Err(e) => Err(warp::reject::custom(e)),
-    }
+use super::{
+    bookmark::mark_bookmarked,
+    etag::{caller_etag, check_if_match, etag, is_not_modified},
+    privilege::{require_known_tags, require_privilege},
+};
+
+#[utoipa::path(
+    get,
+    context_path = "/v1",
+    path = "/questions",
+    tag = "questions",
+    security((), ("token" = [])),
+    responses(
+        (status = 200, description = "All questions; `bookmarked` is set when signed in", body = [Question]),
+        (status = 401, description = "Invalid token", body = ErrorBody),
+    )
+)]
+#[instrument(skip_all)]
+pub async fn get_questions(
+    auth: Option<AuthPayload>,
+    store: DbStore,
+) -> Result<impl Reply, Rejection> {
+    let mut questions = store.get_questions().await?;
+    mark_bookmarked(&store, auth.as_ref(), &mut questions).await?;
+
+    Ok(warp::reply::json(&questions))
 }
 
-pub async fn get_question(id: Uuid, store: DbStore) -> Result<impl Reply, Rejection> {
-    match store.get_question(id).await {
-        Ok(question) => Ok(warp::reply::json(&question)),
-        Err(e) => Err(warp::reject::custom(e)),
+#[utoipa::path(
+    get,
+    context_path = "/v1",
+    path = "/questions/{id}",
+    tag = "questions",
+    params(
+        ("id" = Uuid, Path, description = "Question id"),
+        ("If-None-Match" = Option<String>, Header, description = "ETag of a cached copy"),
+    ),
+    security((), ("token" = [])),
+    responses(
+        (status = 200, description = "The question; `bookmarked` is set when signed in", body = Question,
+            headers(
+                ("ETag" = String, description = "Current version, and the bookmark state when signed in"),
+                ("Vary" = String, description = "`Authorization`"),
+            )),
+        (status = 304, description = "The cached copy is current"),
+        (status = 401, description = "Invalid token", body = ErrorBody),
+        (status = 404, description = "No such question", body = ErrorBody),
+    )
+)]
+#[instrument(skip_all, fields(question_id = %id))]
+pub async fn get_question(
+    id: Uuid,
+    if_none_match: Option<String>,
+    auth: Option<AuthPayload>,
+    store: DbStore,
+) -> Result<impl Reply, Rejection> {
+    let mut question = store.get_question(id).await?;
+    mark_bookmarked(&store, auth.as_ref(), std::slice::from_mut(&mut question)).await?;
+    let tag = question_etag(&question);
+
+    // The body depends on who asks, so shared caches must key on the token.
+    let reply = if is_not_modified(if_none_match, &tag) {
+        warp::reply::with_header(StatusCode::NOT_MODIFIED, "ETag", tag).into_response()
+    } else {
+        warp::reply::with_header(warp::reply::json(&question), "ETag", tag).into_response()
+    };
+    Ok(warp::reply::with_header(reply, "Vary", "Authorization").into_response())
+}
+
+/// The version's tag, told apart by the caller's `bookmarked` flag so that a
+/// cached copy is revalidated when the bookmark changes.
+fn question_etag(question: &Question) -> String {
+    match question.bookmarked {
+        None => etag(question.version),
+        Some(true) => caller_etag(question.version, "bookmarked"),
+        Some(false) => caller_etag(question.version, "not-bookmarked"),
     }
 }
 
+#[utoipa::path(
+    post,
+    context_path = "/v1",
+    path = "/questions",
+    tag = "questions",
+    request_body = NewQuestion,
+    security(("token" = [])),
+    responses(
+        (status = 200, description = "The created question", body = Question),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody),
+        (status = 403, description = "Not enough reputation to create a new tag", body = ErrorBody),
+    )
+)]
+#[instrument(skip_all)]
 pub async fn add_question(
     auth: AuthPayload,
     store: DbStore,
+    config: Config,
+    metrics: Metrics,
     input: NewQuestion,
 ) -> Result<impl Reply, Rejection> {
+    require_known_tags(&store, &config.privileges, &auth, input.tags.as_deref()).await?;
+
     let question = Question {
         id: Uuid::new_v4(),
         title: input.title,
+        content_html: markdown::render(&input.content),
         content: input.content,
         tags: input.tags,
+        version: 1,
+        status: QuestionStatus::Open,
+        close_reason: None,
+        duplicate_of: None,
+        bookmarked: Some(false),
     };
 
     match store.add_question(&question, auth.user_id).await {
-        Ok(_) => Ok(warp::reply::json(&question)),
+        Ok(_) => {
+            metrics.questions_created.inc();
+            Ok(warp::reply::json(&question))
+        }
         Err(e) => Err(warp::reject::custom(e)),
     }
 }
 
+#[utoipa::path(
+    put,
+    context_path = "/v1",
+    path = "/questions/{id}",
+    tag = "questions",
+    params(
+        ("id" = Uuid, Path, description = "Question id"),
+        ("If-Match" = String, Header, description = "ETag the update is based on"),
+    ),
+    request_body = NewQuestion,
+    security(("token" = [])),
+    responses(
+        (status = 200, description = "The updated question", body = Question,
+            headers(("ETag" = String, description = "New version"))),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody),
+        (status = 403, description = "Not enough reputation to edit others' questions or create a new tag", body = ErrorBody),
+        (status = 404, description = "No such question", body = ErrorBody),
+        (status = 409, description = "The question is locked", body = ErrorBody),
+        (status = 412, description = "The question was modified since", body = ErrorBody),
+        (status = 428, description = "If-Match is missing", body = ErrorBody),
+    )
+)]
+#[instrument(skip_all, fields(question_id = %id))]
 pub async fn update_question(
     id: Uuid,
     auth: AuthPayload,
+    if_match: Option<String>,
     store: DbStore,
+    config: Config,
     input: NewQuestion,
 ) -> Result<impl Reply, Rejection> {
-    if !store.is_question_owner(id, auth.user_id).await? {
-        return Err(warp::reject::custom(Error::NotOwner));
+    // Privileges are checked before the row is locked, so that their queries
+    // don't hold up other writers of the question.
+    require_known_tags(&store, &config.privileges, &auth, input.tags.as_deref()).await?;
+    let checked_author = store.get_question_author(id).await?;
+    if checked_author != Some(auth.user_id) {
+        require_privilege(&store, &config.privileges, &auth, Privilege::EditOthers).await?;
     }
 
+    let mut tx = store.begin().await?;
+    let (author_id, version, status) = tx.lock_question_author(id).await?;
+    if status == QuestionStatus::Locked {
+        return Err(Error::QuestionNotOpen(status).into());
+    }
+    // Reassigned since the check above; the caller retries against the new author.
+    if author_id != checked_author {
+        return Err(Error::PreconditionFailed.into());
+    }
+    check_if_match(if_match, version)?;
+
     let question = Question {
         id,
         title: input.title,
+        content_html: markdown::render(&input.content),
         content: input.content,
         tags: input.tags,
+        version,
+        status,
+        close_reason: None,
+        duplicate_of: None,
+        bookmarked: None,
     };
 
-    match store.update_quesiton(question).await {
-        Ok(question) => Ok(warp::reply::json(&question)),
-        Err(e) => Err(warp::reject::custom(e)),
-    }
+    let mut question = tx.update_question(question).await?;
+    tx.commit().await?;
+    mark_bookmarked(&store, Some(&auth), std::slice::from_mut(&mut question)).await?;
+
+    let tag = question_etag(&question);
+    Ok(warp::reply::with_header(
+        warp::reply::json(&question),
+        "ETag",
+        tag,
+    ))
 }
 
+#[utoipa::path(
+    delete,
+    context_path = "/v1",
+    path = "/questions/{id}",
+    tag = "questions",
+    params(
+        ("id" = Uuid, Path, description = "Question id"),
+        ("If-Match" = String, Header, description = "ETag the deletion is based on"),
+    ),
+    security(("token" = [])),
+    responses(
+        (status = 200, description = "Deleted along with its answers", body = bool, content_type = "application/json"),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody),
+        (status = 403, description = "Not the author", body = ErrorBody),
+        (status = 404, description = "No such question", body = ErrorBody),
+        (status = 412, description = "The question was modified since", body = ErrorBody),
+        (status = 428, description = "If-Match is missing", body = ErrorBody),
+    )
+)]
+#[instrument(skip_all, fields(question_id = %id))]
 pub async fn delete_question(
     id: Uuid,
     auth: AuthPayload,
+    if_match: Option<String>,
     store: DbStore,
+    storage: Arc<dyn Storage>,
 ) -> Result<impl Reply, Rejection> {
-    if !store.is_question_owner(id, auth.user_id).await? {
-        return Err(warp::reject::custom(Error::NotOwner));
-    }
+    let mut tx = store.begin().await?;
+    let version = tx.lock_question(id, auth.user_id).await?;
+    check_if_match(if_match, version)?;
+    let attachments = tx.delete_question(id).await?;
+    tx.commit().await?;
 
-    match store.delete_question(id).await {
-        Ok(_) => Ok(warp::reply::json(&true)),
-        Err(e) => Err(warp::reject::custom(e)),
-    }
+    delete_bodies(storage.as_ref(), attachments).await;
+
+    Ok(warp::reply::json(&true))
 }
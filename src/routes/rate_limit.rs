@@ -0,0 +1,107 @@
+use std::{
+    collections::HashMap,
+    future,
+    net::{IpAddr, SocketAddr},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use warp::{reject::Rejection, Filter};
+
+use crate::error::Error;
+
+const WINDOW: Duration = Duration::from_secs(60);
+
+/// Limits each client address to `per_minute` requests in a fixed one minute
+/// window. Passes everything through when `per_minute` is `None`.
+pub fn rate_limit(per_minute: Option<u32>) -> impl Filter<Extract = (), Error = Rejection> + Clone {
+    let windows: Arc<Mutex<HashMap<IpAddr, (Instant, u32)>>> = Default::default();
+
+    warp::addr::remote()
+        .and_then(move |addr: Option<SocketAddr>| {
+            let allowed = match (per_minute, addr) {
+                (Some(limit), Some(addr)) => take(&windows, addr.ip(), limit, Instant::now()),
+                _ => true,
+            };
+
+            if allowed {
+                future::ready(Ok(()))
+            } else {
+                future::ready(Err(warp::reject::custom(Error::RateLimited)))
+            }
+        })
+        .untuple_one()
+}
+
+/// Counts a request from `ip` at `now`; `false` once the window is used up.
+fn take(
+    windows: &Mutex<HashMap<IpAddr, (Instant, u32)>>,
+    ip: IpAddr,
+    limit: u32,
+    now: Instant,
+) -> bool {
+    let mut windows = windows.lock().unwrap();
+
+    if windows.len() > 10_000 {
+        windows.retain(|_, (start, _)| now.duration_since(*start) < WINDOW);
+    }
+
+    let (start, count) = windows.entry(ip).or_insert((now, 0));
+    if now.duration_since(*start) >= WINDOW {
+        *start = now;
+        *count = 0;
+    }
+
+    *count += 1;
+    *count <= limit
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn allows_the_limit_per_window() {
+        let windows = Mutex::default();
+        let ip = [10, 0, 0, 1].into();
+        let now = Instant::now();
+
+        assert!((0..3).all(|_| take(&windows, ip, 3, now)));
+        assert!(!take(&windows, ip, 3, now + Duration::from_secs(59)));
+        assert!(take(&windows, ip, 3, now + WINDOW));
+    }
+
+    #[test]
+    fn counts_each_address_separately() {
+        let windows = Mutex::default();
+        let now = Instant::now();
+
+        assert!(take(&windows, [10, 0, 0, 1].into(), 1, now));
+        assert!(!take(&windows, [10, 0, 0, 1].into(), 1, now));
+        assert!(take(&windows, [10, 0, 0, 2].into(), 1, now));
+    }
+
+    #[tokio::test]
+    async fn rejects_requests_over_the_limit() {
+        let filter = rate_limit(Some(2)).map(warp::reply);
+        let request = || warp::test::request().remote_addr(([10, 0, 0, 1], 4000).into());
+
+        assert_eq!(request().reply(&filter).await.status(), 200);
+        assert_eq!(request().reply(&filter).await.status(), 200);
+        let rejection = request().filter(&filter).await.err().unwrap();
+        assert!(matches!(rejection.find(), Some(Error::RateLimited)));
+    }
+
+    #[tokio::test]
+    async fn passes_everything_when_disabled() {
+        let filter = rate_limit(None).map(warp::reply);
+
+        for _ in 0..10 {
+            let response = warp::test::request()
+                .remote_addr(([10, 0, 0, 1], 4000).into())
+                .reply(&filter)
+                .await;
+            assert_eq!(response.status(), 200);
+        }
+    }
+}
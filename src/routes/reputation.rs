@@ -0,0 +1,237 @@
+use tracing::instrument;
+use uuid::Uuid;
+use warp::{reject::Rejection, reply::Reply};
+
+use crate::{
+    config::Config,
+    domain::{
+        page::{Cursor, Page, PageQuery},
+        reputation::{NewVote, Privilege, ReputationEvent, VoteTarget},
+        user::AuthPayload,
+    },
+    error::Error,
+    store::DbStore,
+};
+
+use super::{follow::page_cursor, privilege::require_privilege};
+
+async fn vote(
+    target: VoteTarget,
+    auth: AuthPayload,
+    store: DbStore,
+    config: Config,
+    input: NewVote,
+) -> Result<impl Reply, Rejection> {
+    if input.value != 1 && input.value != -1 {
+        return Err(Error::InvalidInput("`value` must be 1 or -1".to_string()).into());
+    }
+    if input.value < 0 {
+        require_privilege(&store, &config.privileges, &auth, Privilege::Downvote).await?;
+    }
+
+    let result = store
+        .vote(auth.user_id, target, input.value, &config.reputation)
+        .await?;
+    Ok(warp::reply::json(&result))
+}
+
+#[utoipa::path(
+    put,
+    context_path = "/v1",
+    path = "/questions/{id}/vote",
+    tag = "reputation",
+    params(("id" = Uuid, Path, description = "Question id")),
+    request_body = NewVote,
+    security(("token" = [])),
+    responses(
+        (status = 200, description = "The question's new score", body = VoteResult),
+        (status = 400, description = "Not 1 or -1, or your own question", body = ErrorBody),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody),
+        (status = 403, description = "Not enough reputation to downvote", body = ErrorBody),
+        (status = 404, description = "No such question", body = ErrorBody),
+    )
+)]
+#[instrument(skip_all, fields(question_id = %id))]
+pub async fn vote_question(
+    id: Uuid,
+    auth: AuthPayload,
+    store: DbStore,
+    config: Config,
+    input: NewVote,
+) -> Result<impl Reply, Rejection> {
+    vote(VoteTarget::Question(id), auth, store, config, input).await
+}
+
+#[utoipa::path(
+    delete,
+    context_path = "/v1",
+    path = "/questions/{id}/vote",
+    tag = "reputation",
+    params(("id" = Uuid, Path, description = "Question id")),
+    security(("token" = [])),
+    responses(
+        (status = 200, description = "The question's new score", body = VoteResult),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody),
+        (status = 404, description = "No such question", body = ErrorBody),
+    )
+)]
+#[instrument(skip_all, fields(question_id = %id))]
+pub async fn unvote_question(
+    id: Uuid,
+    auth: AuthPayload,
+    store: DbStore,
+) -> Result<impl Reply, Rejection> {
+    let result = store.unvote(auth.user_id, VoteTarget::Question(id)).await?;
+    Ok(warp::reply::json(&result))
+}
+
+#[utoipa::path(
+    put,
+    context_path = "/v1",
+    path = "/answers/{id}/vote",
+    tag = "reputation",
+    params(("id" = Uuid, Path, description = "Answer id")),
+    request_body = NewVote,
+    security(("token" = [])),
+    responses(
+        (status = 200, description = "The answer's new score", body = VoteResult),
+        (status = 400, description = "Not 1 or -1, or your own answer", body = ErrorBody),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody),
+        (status = 403, description = "Not enough reputation to downvote", body = ErrorBody),
+        (status = 404, description = "No such answer", body = ErrorBody),
+    )
+)]
+#[instrument(skip_all, fields(answer_id = %id))]
+pub async fn vote_answer(
+    id: Uuid,
+    auth: AuthPayload,
+    store: DbStore,
+    config: Config,
+    input: NewVote,
+) -> Result<impl Reply, Rejection> {
+    vote(VoteTarget::Answer(id), auth, store, config, input).await
+}
+
+#[utoipa::path(
+    delete,
+    context_path = "/v1",
+    path = "/answers/{id}/vote",
+    tag = "reputation",
+    params(("id" = Uuid, Path, description = "Answer id")),
+    security(("token" = [])),
+    responses(
+        (status = 200, description = "The answer's new score", body = VoteResult),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody),
+        (status = 404, description = "No such answer", body = ErrorBody),
+    )
+)]
+#[instrument(skip_all, fields(answer_id = %id))]
+pub async fn unvote_answer(
+    id: Uuid,
+    auth: AuthPayload,
+    store: DbStore,
+) -> Result<impl Reply, Rejection> {
+    let result = store.unvote(auth.user_id, VoteTarget::Answer(id)).await?;
+    Ok(warp::reply::json(&result))
+}
+
+#[utoipa::path(
+    put,
+    context_path = "/v1",
+    path = "/answers/{id}/accept",
+    tag = "reputation",
+    params(("id" = Uuid, Path, description = "Answer id")),
+    security(("token" = [])),
+    responses(
+        (status = 200, description = "The accepted answer", body = Answer),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody),
+        (status = 403, description = "Not the question's author", body = ErrorBody),
+        (status = 404, description = "No such answer", body = ErrorBody),
+    )
+)]
+#[instrument(skip_all, fields(answer_id = %id))]
+pub async fn accept_answer(
+    id: Uuid,
+    auth: AuthPayload,
+    store: DbStore,
+    config: Config,
+) -> Result<impl Reply, Rejection> {
+    let answer = store
+        .accept_answer(auth.user_id, id, &config.reputation)
+        .await?;
+    Ok(warp::reply::json(&answer))
+}
+
+#[utoipa::path(
+    delete,
+    context_path = "/v1",
+    path = "/answers/{id}/accept",
+    tag = "reputation",
+    params(("id" = Uuid, Path, description = "Answer id")),
+    security(("token" = [])),
+    responses(
+        (status = 200, description = "The answer is no longer accepted", body = bool, content_type = "application/json"),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody),
+        (status = 403, description = "Not the question's author", body = ErrorBody),
+        (status = 404, description = "No such answer", body = ErrorBody),
+    )
+)]
+#[instrument(skip_all, fields(answer_id = %id))]
+pub async fn unaccept_answer(
+    id: Uuid,
+    auth: AuthPayload,
+    store: DbStore,
+) -> Result<impl Reply, Rejection> {
+    store.unaccept_answer(auth.user_id, id).await?;
+    Ok(warp::reply::json(&true))
+}
+
+#[utoipa::path(
+    get,
+    context_path = "/v1",
+    path = "/users/{id}",
+    tag = "reputation",
+    params(("id" = Uuid, Path, description = "User id")),
+    responses(
+        (status = 200, description = "Public profile with reputation and badges", body = UserProfile),
+        (status = 404, description = "No such user", body = ErrorBody),
+    )
+)]
+#[instrument(skip_all, fields(user_id = %id))]
+pub async fn get_user_profile(id: Uuid, store: DbStore) -> Result<impl Reply, Rejection> {
+    let profile = store.get_user_profile(id).await?;
+    Ok(warp::reply::json(&profile))
+}
+
+#[utoipa::path(
+    get,
+    context_path = "/v1",
+    path = "/users/{id}/reputation",
+    tag = "reputation",
+    params(
+        ("id" = Uuid, Path, description = "User id"),
+        ("limit" = Option<i64>, Query, description = "At most 100, default 20"),
+        ("before" = Option<String>, Query, description = "`next` of the previous page"),
+    ),
+    responses(
+        (status = 200, description = "Reputation ledger, newest first", body = ReputationPage),
+        (status = 400, description = "Malformed cursor", body = ErrorBody),
+    )
+)]
+#[instrument(skip_all, fields(user_id = %id))]
+pub async fn get_reputation_events(
+    id: Uuid,
+    store: DbStore,
+    query: PageQuery,
+) -> Result<impl Reply, Rejection> {
+    let cursor = page_cursor(&query)?;
+    let limit = query.limit();
+
+    let events = store.get_reputation_events(id, cursor, limit + 1).await?;
+    let page = Page::new(events, limit, |event: &ReputationEvent| Cursor {
+        created_on: event.created_on,
+        id: event.id,
+    });
+
+    Ok(warp::reply::json(&page))
+}
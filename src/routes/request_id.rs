@@ -0,0 +1,42 @@
+use opentelemetry::global;
+use tracing::Span;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use uuid::Uuid;
+use warp::{reject::Rejection, trace::Info, Filter};
+
+use crate::telemetry::HeaderExtractor;
+
+/// Span covering a whole request, including handler and store calls. It
+/// continues the caller's trace when a W3C `traceparent` header is present.
+pub fn request_span(info: Info) -> Span {
+    let span = tracing::info_span!(
+        "request",
+        method = %info.method(),
+        path = info.path(),
+        request_id = tracing::field::Empty,
+    );
+
+    let parent = global::get_text_map_propagator(|propagator| {
+        propagator.extract(&HeaderExtractor(info.request_headers()))
+    });
+    span.set_parent(parent);
+
+    span
+}
+
+/// Takes the client's `X-Request-Id` when it is usable, otherwise generates
+/// one, and records it on the request span.
+pub fn request_id() -> impl Filter<Extract = (String,), Error = Rejection> + Clone {
+    warp::header::optional::<String>("x-request-id").map(|id: Option<String>| {
+        let id = id
+            .filter(|id| is_valid(id))
+            .unwrap_or_else(|| Uuid::new_v4().to_string());
+
+        Span::current().record("request_id", id.as_str());
+        id
+    })
+}
+
+fn is_valid(id: &str) -> bool {
+    !id.is_empty() && id.len() <= 128 && id.bytes().all(|b| b.is_ascii_graphic())
+}
@@ -0,0 +1,132 @@
+use tracing::instrument;
+use uuid::Uuid;
+use warp::{reject::Rejection, reply::Reply};
+
+use crate::{
+    config::Config,
+    domain::{
+        question::{CloseReason, NewCloseVote, QuestionStatus, StatusChange},
+        user::AuthPayload,
+    },
+    error::Error,
+    store::DbStore,
+};
+
+use super::user::require_moderator;
+
+/// Checks that `duplicate_of` is given exactly when the reason is
+/// `duplicate`, and doesn't point back at the question.
+fn check_duplicate_of(
+    id: Uuid,
+    reason: Option<CloseReason>,
+    duplicate_of: Option<Uuid>,
+) -> Result<(), Error> {
+    let message = match (reason, duplicate_of) {
+        (_, Some(original)) if original == id => "a question can't duplicate itself",
+        (Some(CloseReason::Duplicate), None) => "`duplicate` needs `duplicate_of`",
+        (Some(CloseReason::Duplicate), Some(_)) | (_, None) => return Ok(()),
+        (_, Some(_)) => "`duplicate_of` only goes with the `duplicate` reason",
+    };
+
+    Err(Error::InvalidInput(message.to_string()))
+}
+
+#[utoipa::path(
+    put,
+    context_path = "/v1",
+    path = "/questions/{id}/close-vote",
+    tag = "questions",
+    params(("id" = Uuid, Path, description = "Question id")),
+    request_body = NewCloseVote,
+    security(("token" = [])),
+    responses(
+        (status = 200, description = "Votes so far; `status` is `closed` once enough are in", body = CloseVotes),
+        (status = 400, description = "`duplicate_of` is missing, misplaced or unknown", body = ErrorBody),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody),
+        (status = 403, description = "Not enough reputation to vote to close", body = ErrorBody),
+        (status = 404, description = "No such question", body = ErrorBody),
+        (status = 409, description = "The question is already closed or locked", body = ErrorBody),
+    )
+)]
+#[instrument(skip_all, fields(question_id = %id))]
+pub async fn add_close_vote(
+    id: Uuid,
+    auth: AuthPayload,
+    store: DbStore,
+    config: Config,
+    input: NewCloseVote,
+) -> Result<impl Reply, Rejection> {
+    check_duplicate_of(id, Some(input.reason), input.duplicate_of)?;
+
+    let votes = store
+        .add_close_vote(auth.user_id, id, &input, config.close_votes)
+        .await?;
+    Ok(warp::reply::json(&votes))
+}
+
+#[utoipa::path(
+    delete,
+    context_path = "/v1",
+    path = "/questions/{id}/close-vote",
+    tag = "questions",
+    params(("id" = Uuid, Path, description = "Question id")),
+    security(("token" = [])),
+    responses(
+        (status = 200, description = "Votes left", body = CloseVotes),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody),
+        (status = 404, description = "No such question", body = ErrorBody),
+    )
+)]
+#[instrument(skip_all, fields(question_id = %id))]
+pub async fn delete_close_vote(
+    id: Uuid,
+    auth: AuthPayload,
+    store: DbStore,
+    config: Config,
+) -> Result<impl Reply, Rejection> {
+    let votes = store
+        .delete_close_vote(auth.user_id, id, config.close_votes)
+        .await?;
+    Ok(warp::reply::json(&votes))
+}
+
+#[utoipa::path(
+    put,
+    context_path = "/v1",
+    path = "/questions/{id}/status",
+    tag = "questions",
+    params(("id" = Uuid, Path, description = "Question id")),
+    request_body = StatusChange,
+    security(("token" = [])),
+    responses(
+        (status = 200, description = "The question with its new status", body = Question),
+        (status = 400, description = "Reason or `duplicate_of` don't fit the status", body = ErrorBody),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody),
+        (status = 403, description = "Not a moderator or admin", body = ErrorBody),
+        (status = 404, description = "No such question", body = ErrorBody),
+    )
+)]
+#[instrument(skip_all, fields(question_id = %id))]
+pub async fn set_question_status(
+    id: Uuid,
+    auth: AuthPayload,
+    store: DbStore,
+    input: StatusChange,
+) -> Result<impl Reply, Rejection> {
+    require_moderator(&store, &auth).await?;
+
+    match (input.status, input.reason) {
+        (QuestionStatus::Open, Some(_)) => {
+            return Err(Error::InvalidInput("reopening takes no `reason`".to_string()).into())
+        }
+        (QuestionStatus::Closed, None) => {
+            return Err(Error::InvalidInput("closing needs a `reason`".to_string()).into())
+        }
+        _ => check_duplicate_of(id, input.reason, input.duplicate_of)?,
+    }
+
+    let question = store
+        .set_question_status(id, input.status, input.reason, input.duplicate_of)
+        .await?;
+    Ok(warp::reply::json(&question))
+}
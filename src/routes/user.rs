@@ -1,24 +1,26 @@
-use std::env;
-use std::future;
-
-use chrono::{prelude::*, Days};
+use chrono::{prelude::*, Duration};
 use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
-use rand::Rng;
+use tracing::instrument;
 use uuid::Uuid;
 use warp::{reject::Rejection, reply::Reply, Filter};
 
 use crate::{
-    domain::user::{AuthPayload, Credential, NewUser, User},
+    config::Config,
+    domain::user::{AuthPayload, Credential, NewUser, Role, User},
     error::Error,
-    store::DbStore,
+    metrics::Metrics,
+    store::{hash_password, DbStore},
 };
 
-fn hash_password(password: &[u8]) -> String {
-    let salt = rand::thread_rng().gen::<[u8; 32]>();
-    let config = argon2::Config::default();
-    argon2::hash_encoded(password, &salt, &config).unwrap()
-}
-
+#[utoipa::path(
+    post,
+    context_path = "/v1",
+    path = "/signup",
+    tag = "users",
+    request_body = NewUser,
+    responses((status = 200, description = "Account created", body = bool, content_type = "application/json"))
+)]
+#[instrument(skip_all)]
 pub async fn signup(store: DbStore, input: NewUser) -> Result<impl Reply, Rejection> {
     let hashed_password = hash_password(input.password.as_bytes());
 
@@ -27,6 +29,8 @@ pub async fn signup(store: DbStore, input: NewUser) -> Result<impl Reply, Reject
         name: input.name,
         email: input.email,
         password: hashed_password,
+        role: Role::User,
+        disabled: false,
     };
 
     match store.add_user(user).await {
@@ -35,20 +39,45 @@ pub async fn signup(store: DbStore, input: NewUser) -> Result<impl Reply, Reject
     }
 }
 
-pub async fn signin(store: DbStore, input: Credential) -> Result<impl Reply, Rejection> {
+#[utoipa::path(
+    post,
+    context_path = "/v1",
+    path = "/signin",
+    tag = "users",
+    request_body = Credential,
+    responses(
+        (status = 200, description = "Token to send as the Authorization header", body = String, content_type = "application/json"),
+        (status = 401, description = "Invalid email or password", body = ErrorBody),
+        (status = 403, description = "Account is disabled", body = ErrorBody),
+    )
+)]
+#[instrument(skip_all)]
+pub async fn signin(
+    store: DbStore,
+    config: Config,
+    metrics: Metrics,
+    input: Credential,
+) -> Result<impl Reply, Rejection> {
     match store.find_user_by_credential(input).await {
         Ok(user) => {
-            let token = sign_token(user.id)?;
+            let token = sign_token(user.id, &config)?;
             Ok(warp::reply::json(&token))
         }
 
-        Err(e) => Err(warp::reject::custom(e)),
+        Err(e) => {
+            if let Error::InvalidEmailPassword = e {
+                metrics.signins_failed.inc();
+            }
+            Err(warp::reject::custom(e))
+        }
     }
 }
 
-fn sign_token(user_id: Uuid) -> Result<String, Error> {
+fn sign_token(user_id: Uuid, config: &Config) -> Result<String, Error> {
     let now = Utc::now();
-    let exp = now.checked_add_days(Days::new(7)).unwrap();
+    let exp = Duration::try_hours(config.token_ttl_hours)
+        .and_then(|ttl| now.checked_add_signed(ttl))
+        .ok_or(Error::JwtError)?;
 
     let payload = AuthPayload {
         user_id,
@@ -58,15 +87,15 @@ fn sign_token(user_id: Uuid) -> Result<String, Error> {
     encode(
         &Header::default(),
         &payload,
-        &EncodingKey::from_secret(env::var("JWT_SECRET").unwrap().as_ref()),
+        &EncodingKey::from_secret(config.jwt_secret.as_ref()),
     )
     .map_err(|_| Error::JwtError)
 }
 
-fn verify_token(token: String) -> Result<AuthPayload, Error> {
+fn verify_token(token: String, secret: &str) -> Result<AuthPayload, Error> {
     let payload = decode::<AuthPayload>(
         &token,
-        &DecodingKey::from_secret(env::var("JWT_SECRET").unwrap().as_ref()),
+        &DecodingKey::from_secret(secret.as_ref()),
         &Validation::default(),
     )
     .map_err(|_| Error::JwtError)?;
@@ -74,13 +103,74 @@ fn verify_token(token: String) -> Result<AuthPayload, Error> {
     Ok(payload.claims)
 }
 
-pub fn protect() -> impl Filter<Extract = (AuthPayload,), Error = warp::Rejection> + Clone {
-    warp::header::<String>("Authorization").and_then(|token: String| {
-        let payload = match verify_token(token) {
-            Ok(t) => t,
-            Err(e) => return future::ready(Err(warp::reject::custom(e))),
-        };
+/// Accepts a valid token of an account that still exists and isn't
+/// disabled; disabling a user revokes their tokens immediately.
+pub fn protect(
+    config: &Config,
+    store: &DbStore,
+) -> impl Filter<Extract = (AuthPayload,), Error = warp::Rejection> + Clone {
+    let secret = config.jwt_secret.clone();
+    let store = store.clone();
 
-        future::ready(Ok(payload))
+    warp::header::<String>("Authorization").and_then(move |token: String| {
+        let secret = secret.clone();
+        let store = store.clone();
+        async move { authenticate(token, &secret, &store).await }
+    })
+}
+
+/// Like [`protect`], but lets requests without an `Authorization` header
+/// through as anonymous. A header with an invalid token is still rejected.
+pub fn optional_auth(
+    config: &Config,
+    store: &DbStore,
+) -> impl Filter<Extract = (Option<AuthPayload>,), Error = warp::Rejection> + Clone {
+    let secret = config.jwt_secret.clone();
+    let store = store.clone();
+
+    warp::header::optional::<String>("Authorization").and_then(move |token: Option<String>| {
+        let secret = secret.clone();
+        let store = store.clone();
+        async move {
+            match token {
+                Some(token) => authenticate(token, &secret, &store).await.map(Some),
+                None => Ok(None),
+            }
+        }
     })
 }
+
+async fn authenticate(
+    token: String,
+    secret: &str,
+    store: &DbStore,
+) -> Result<AuthPayload, Rejection> {
+    let payload = verify_token(token, secret)?;
+
+    match store.is_user_disabled(payload.user_id).await {
+        Ok(false) => Ok(payload),
+        Ok(true) => Err(Error::AccountDisabled.into()),
+        // Deleted since the token was issued.
+        Err(Error::NotFound) => Err(Error::JwtError.into()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Fails with [`Error::AdminOnly`] unless the signed-in user is an admin.
+pub(super) async fn require_admin(store: &DbStore, auth: &AuthPayload) -> Result<(), Error> {
+    match store.get_user_role(auth.user_id).await {
+        Ok(Role::Admin) => Ok(()),
+        Ok(_) | Err(Error::NotFound) => Err(Error::AdminOnly),
+        Err(e) => Err(e),
+    }
+}
+
+/// Fails with [`Error::ModeratorOnly`] unless the signed-in user is a
+/// moderator or an admin.
+pub(super) async fn require_moderator(store: &DbStore, auth: &AuthPayload) -> Result<(), Error> {
+    match store.get_user_role(auth.user_id).await {
+        Ok(Role::Moderator | Role::Admin) => Ok(()),
+        Ok(_) | Err(Error::NotFound) => Err(Error::ModeratorOnly),
+        Err(e) => Err(e),
+    }
+}
@@ -0,0 +1,156 @@
+use chrono::Utc;
+use rand::{distributions::Alphanumeric, Rng};
+use reqwest::Url;
+use tracing::instrument;
+use uuid::Uuid;
+use warp::{reject::Rejection, reply::Reply};
+
+use crate::{
+    domain::{
+        user::AuthPayload,
+        webhook::{NewWebhook, Webhook},
+    },
+    error::Error,
+    store::DbStore,
+};
+
+use super::user::require_admin;
+
+/// Deliveries listed per webhook.
+const DELIVERY_LOG_LIMIT: i64 = 100;
+
+#[utoipa::path(
+    post,
+    context_path = "/v1",
+    path = "/webhooks",
+    tag = "webhooks",
+    request_body = NewWebhook,
+    security(("token" = [])),
+    responses(
+        (status = 200, description = "The webhook, including its secret", body = Webhook),
+        (status = 400, description = "Invalid URL or no events", body = ErrorBody),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody),
+        (status = 403, description = "Not an admin", body = ErrorBody),
+    )
+)]
+#[instrument(skip_all)]
+pub async fn add_webhook(
+    auth: AuthPayload,
+    store: DbStore,
+    input: NewWebhook,
+) -> Result<impl Reply, Rejection> {
+    require_admin(&store, &auth).await?;
+
+    match Url::parse(&input.url) {
+        Ok(url) if matches!(url.scheme(), "http" | "https") => {}
+        _ => {
+            return Err(warp::reject::custom(Error::InvalidInput(
+                "url must be an absolute http(s) URL".to_string(),
+            )))
+        }
+    }
+
+    let mut events: Vec<String> = input
+        .events
+        .iter()
+        .map(|e| e.as_str().to_string())
+        .collect();
+    events.sort();
+    events.dedup();
+    if events.is_empty() {
+        return Err(warp::reject::custom(Error::InvalidInput(
+            "events must not be empty".to_string(),
+        )));
+    }
+
+    let secret = match input.secret {
+        Some(secret) if !secret.is_empty() => secret,
+        _ => rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(32)
+            .map(char::from)
+            .collect(),
+    };
+
+    let webhook = Webhook {
+        id: Uuid::new_v4(),
+        url: input.url,
+        events,
+        secret: Some(secret),
+        created_on: Utc::now().naive_utc(),
+    };
+    store.add_webhook(&webhook, auth.user_id).await?;
+
+    Ok(warp::reply::json(&webhook))
+}
+
+#[utoipa::path(
+    get,
+    context_path = "/v1",
+    path = "/webhooks",
+    tag = "webhooks",
+    security(("token" = [])),
+    responses(
+        (status = 200, description = "All webhooks, without secrets", body = [Webhook]),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody),
+        (status = 403, description = "Not an admin", body = ErrorBody),
+    )
+)]
+#[instrument(skip_all)]
+pub async fn get_webhooks(auth: AuthPayload, store: DbStore) -> Result<impl Reply, Rejection> {
+    require_admin(&store, &auth).await?;
+
+    let webhooks = store.get_webhooks().await?;
+    Ok(warp::reply::json(&webhooks))
+}
+
+#[utoipa::path(
+    delete,
+    context_path = "/v1",
+    path = "/webhooks/{id}",
+    tag = "webhooks",
+    params(("id" = Uuid, Path, description = "Webhook id")),
+    security(("token" = [])),
+    responses(
+        (status = 200, description = "Deleted with its delivery log", body = bool, content_type = "application/json"),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody),
+        (status = 403, description = "Not an admin", body = ErrorBody),
+        (status = 404, description = "No such webhook", body = ErrorBody),
+    )
+)]
+#[instrument(skip_all, fields(webhook_id = %id))]
+pub async fn delete_webhook(
+    id: Uuid,
+    auth: AuthPayload,
+    store: DbStore,
+) -> Result<impl Reply, Rejection> {
+    require_admin(&store, &auth).await?;
+
+    store.delete_webhook(id).await?;
+    Ok(warp::reply::json(&true))
+}
+
+#[utoipa::path(
+    get,
+    context_path = "/v1",
+    path = "/webhooks/{id}/deliveries",
+    tag = "webhooks",
+    params(("id" = Uuid, Path, description = "Webhook id")),
+    security(("token" = [])),
+    responses(
+        (status = 200, description = "The 100 most recent deliveries, newest first", body = [WebhookDelivery]),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody),
+        (status = 403, description = "Not an admin", body = ErrorBody),
+    )
+)]
+#[instrument(skip_all, fields(webhook_id = %id))]
+pub async fn get_webhook_deliveries(
+    id: Uuid,
+    auth: AuthPayload,
+    store: DbStore,
+) -> Result<impl Reply, Rejection> {
+    require_admin(&store, &auth).await?;
+
+    let deliveries = store.get_webhook_deliveries(id, DELIVERY_LOG_LIMIT).await?;
+    Ok(warp::reply::json(&deliveries))
+}
@@ -0,0 +1,46 @@
+//! Stops background tasks once the server has drained.
+
+use std::future;
+
+use tokio::sync::watch;
+
+/// Fires [`Shutdown`]; held by whoever runs the server.
+#[derive(Debug)]
+pub struct Trigger(watch::Sender<bool>);
+
+impl Trigger {
+    pub fn fire(self) {
+        self.0.send(true).ok();
+    }
+}
+
+/// Handed to each background task, which stops once [`Shutdown::wait`]
+/// resolves.
+#[derive(Debug, Clone)]
+pub struct Shutdown(watch::Receiver<bool>);
+
+impl Shutdown {
+    pub fn new() -> (Trigger, Self) {
+        let (sender, receiver) = watch::channel(false);
+        (Trigger(sender), Self(receiver))
+    }
+
+    /// For hosts without a shutdown hook: tasks run as long as the process.
+    pub fn never() -> Self {
+        Self::new().1
+    }
+
+    pub fn is_fired(&self) -> bool {
+        *self.0.borrow()
+    }
+
+    /// Resolves once the trigger fires. A trigger dropped without firing
+    /// never resolves.
+    pub async fn wait(&mut self) {
+        while !*self.0.borrow_and_update() {
+            if self.0.changed().await.is_err() {
+                future::pending::<()>().await;
+            }
+        }
+    }
+}
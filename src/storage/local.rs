@@ -0,0 +1,142 @@
+use std::{io::ErrorKind, path::PathBuf};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use tokio::fs;
+
+use crate::error::Error;
+
+use super::Storage;
+
+/// Keeps each blob in a file named after its key under `root`.
+#[derive(Debug, Clone)]
+pub struct LocalStorage {
+    root: PathBuf,
+}
+
+impl LocalStorage {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    fn path(&self, key: &str) -> Result<PathBuf, Error> {
+        // Keys are generated ids; refuse anything that could leave `root`.
+        if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+            return Err(Error::StorageError(format!("invalid key `{}`", key)));
+        }
+
+        Ok(self.root.join(key))
+    }
+}
+
+#[async_trait]
+impl Storage for LocalStorage {
+    async fn put(&self, key: &str, _content_type: &str, data: Bytes) -> Result<(), Error> {
+        let path = self.path(key)?;
+
+        if let Err(e) = fs::create_dir_all(&self.root).await {
+            return Err(Error::StorageError(e.to_string()));
+        }
+
+        // Write aside and rename so a reader never sees a partial file.
+        let partial = path.with_extension("partial");
+        if let Err(e) = fs::write(&partial, &data).await {
+            return Err(Error::StorageError(e.to_string()));
+        }
+
+        match fs::rename(&partial, &path).await {
+            Ok(_) => Ok(()),
+            Err(e) => Err(Error::StorageError(e.to_string())),
+        }
+    }
+
+    async fn get(&self, key: &str) -> Result<Bytes, Error> {
+        match fs::read(self.path(key)?).await {
+            Ok(data) => Ok(data.into()),
+            Err(e) if e.kind() == ErrorKind::NotFound => Err(Error::NotFound),
+            Err(e) => Err(Error::StorageError(e.to_string())),
+        }
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), Error> {
+        match fs::remove_file(self.path(key)?).await {
+            Ok(_) => Ok(()),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(Error::StorageError(e.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use super::*;
+
+    /// A storage rooted in a fresh directory, removed again on drop.
+    struct Scratch(LocalStorage);
+
+    impl Scratch {
+        fn new() -> Self {
+            let root = std::env::temp_dir().join(format!("askly-storage-{}", Uuid::new_v4()));
+            Scratch(LocalStorage::new(root))
+        }
+    }
+
+    impl Drop for Scratch {
+        fn drop(&mut self) {
+            std::fs::remove_dir_all(&self.0.root).ok();
+        }
+    }
+
+    #[tokio::test]
+    async fn stores_and_deletes_blobs() {
+        let scratch = Scratch::new();
+        let storage = &scratch.0;
+        let key = Uuid::new_v4().to_string();
+
+        storage
+            .put(&key, "text/plain", Bytes::from_static(b"hello"))
+            .await
+            .unwrap();
+        assert_eq!(storage.get(&key).await.unwrap(), "hello");
+
+        storage
+            .put(&key, "text/plain", Bytes::from_static(b"replaced"))
+            .await
+            .unwrap();
+        assert_eq!(storage.get(&key).await.unwrap(), "replaced");
+
+        storage.delete(&key).await.unwrap();
+        assert!(matches!(storage.get(&key).await, Err(Error::NotFound)));
+        // Deleting what isn't there is not an error.
+        storage.delete(&key).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn rejects_keys_that_could_leave_the_root() {
+        let scratch = Scratch::new();
+        let storage = &scratch.0;
+
+        for key in [
+            "",
+            "../escape",
+            "a/b",
+            "/etc/passwd",
+            "key.partial",
+            "..",
+            "a\\b",
+        ] {
+            let put = storage.put(key, "text/plain", Bytes::new()).await;
+            assert!(matches!(put, Err(Error::StorageError(_))), "put {:?}", key);
+            assert!(matches!(
+                storage.get(key).await,
+                Err(Error::StorageError(_))
+            ));
+            assert!(matches!(
+                storage.delete(key).await,
+                Err(Error::StorageError(_))
+            ));
+        }
+    }
+}
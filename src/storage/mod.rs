@@ -0,0 +1,57 @@
+//! Blob storage for attachment bodies. Metadata lives in the `attachments`
+//! table; a blob is stored under the attachment's id.
+
+mod local;
+mod s3;
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::{config::StorageConfig, error::Error};
+
+pub use local::LocalStorage;
+pub use s3::S3Storage;
+
+#[async_trait]
+pub trait Storage: Send + Sync {
+    async fn put(&self, key: &str, content_type: &str, data: Bytes) -> Result<(), Error>;
+
+    /// `Error::NotFound` when nothing is stored under `key`.
+    async fn get(&self, key: &str) -> Result<Bytes, Error>;
+
+    /// Succeeds when nothing is stored under `key`.
+    async fn delete(&self, key: &str) -> Result<(), Error>;
+}
+
+pub fn from_config(config: &StorageConfig) -> Arc<dyn Storage> {
+    match config {
+        StorageConfig::Local { path } => Arc::new(LocalStorage::new(path.clone())),
+        StorageConfig::S3 {
+            endpoint,
+            bucket,
+            region,
+            access_key,
+            secret_key,
+        } => Arc::new(S3Storage::new(
+            endpoint.clone(),
+            bucket.clone(),
+            region.clone(),
+            access_key.clone(),
+            secret_key.clone(),
+        )),
+    }
+}
+
+/// Removes the bodies of attachments whose rows were deleted. Failures only
+/// leave an unreachable file behind, so they are logged and skipped.
+pub async fn delete_bodies(storage: &dyn Storage, ids: Vec<Uuid>) {
+    for id in ids {
+        if let Err(e) = storage.delete(&id.to_string()).await {
+            warn!("failed to delete attachment {}: {}", id, e);
+        }
+    }
+}
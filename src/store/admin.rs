@@ -0,0 +1,225 @@
+//! Operator actions used by the admin CLI; none of these are exposed over HTTP.
+
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{
+    domain::{
+        question::QuestionSummary,
+        user::{Role, User},
+    },
+    error::Error,
+};
+
+use super::{record_rows, DbStore};
+
+impl DbStore {
+    #[instrument(name = "db.find_user_by_email", skip_all, fields(db.rows))]
+    pub async fn find_user_by_email(&self, email: &str) -> Result<User, Error> {
+        match sqlx::query_as!(
+            User,
+            r#"
+            SELECT id, name, email, password, role AS "role: Role", disabled
+            FROM users WHERE email = $1
+            "#,
+            email
+        )
+        .fetch_one(&self.conn)
+        .await
+        {
+            Ok(user) => {
+                record_rows(1);
+                Ok(user)
+            }
+            Err(sqlx::Error::RowNotFound) => Err(Error::NotFound),
+            Err(e) => Err(Error::DbError(e)),
+        }
+    }
+
+    #[instrument(name = "db.set_user_role", skip_all, fields(db.rows))]
+    pub async fn set_user_role(&self, user_id: Uuid, role: Role) -> Result<(), Error> {
+        match sqlx::query!(
+            "UPDATE users SET role = $1 WHERE id = $2",
+            role as Role,
+            user_id
+        )
+        .execute(&self.conn)
+        .await
+        {
+            Ok(result) if result.rows_affected() == 0 => Err(Error::NotFound),
+            Ok(result) => {
+                record_rows(result.rows_affected() as usize);
+                Ok(())
+            }
+            Err(e) => Err(Error::DbError(e)),
+        }
+    }
+
+    /// Stores an already hashed password.
+    #[instrument(name = "db.set_user_password", skip_all, fields(db.rows))]
+    pub async fn set_user_password(&self, user_id: Uuid, password: &str) -> Result<(), Error> {
+        match sqlx::query!(
+            "UPDATE users SET password = $1 WHERE id = $2",
+            password,
+            user_id
+        )
+        .execute(&self.conn)
+        .await
+        {
+            Ok(result) if result.rows_affected() == 0 => Err(Error::NotFound),
+            Ok(result) => {
+                record_rows(result.rows_affected() as usize);
+                Ok(())
+            }
+            Err(e) => Err(Error::DbError(e)),
+        }
+    }
+
+    /// Disabled users cannot sign in, and tokens already issued are rejected.
+    #[instrument(name = "db.set_user_disabled", skip_all, fields(db.rows))]
+    pub async fn set_user_disabled(&self, user_id: Uuid, disabled: bool) -> Result<(), Error> {
+        match sqlx::query!(
+            "UPDATE users SET disabled = $1 WHERE id = $2",
+            disabled,
+            user_id
+        )
+        .execute(&self.conn)
+        .await
+        {
+            Ok(result) if result.rows_affected() == 0 => Err(Error::NotFound),
+            Ok(result) => {
+                record_rows(result.rows_affected() as usize);
+                Ok(())
+            }
+            Err(e) => Err(Error::DbError(e)),
+        }
+    }
+
+    /// Deletes every question and answer of a user, including answers others
+    /// posted to those questions, and their attachments. Returns the number
+    /// of questions and answers removed and the ids of the deleted
+    /// attachments, whose bodies are left for the caller to remove from
+    /// storage.
+    #[instrument(name = "db.delete_user_content", skip_all, fields(db.rows))]
+    pub async fn delete_user_content(&self, user_id: Uuid) -> Result<(u64, Vec<Uuid>), Error> {
+        let mut tx = match self.conn.begin().await {
+            Ok(tx) => tx,
+            Err(e) => return Err(Error::DbError(e)),
+        };
+
+        let attachments = match sqlx::query_scalar!(
+            r"
+            DELETE FROM attachments
+            WHERE question_id IN (SELECT id FROM questions WHERE user_id = $1)
+            OR answer_id IN (
+                SELECT a.id FROM answers a
+                WHERE a.user_id = $1
+                OR a.question_id IN (SELECT id FROM questions WHERE user_id = $1)
+            )
+            RETURNING id
+            ",
+            user_id
+        )
+        .fetch_all(&mut *tx)
+        .await
+        {
+            Ok(ids) => ids,
+            Err(e) => return Err(Error::DbError(e)),
+        };
+
+        let answers = match sqlx::query!(
+            r"
+            DELETE FROM answers
+            WHERE user_id = $1
+            OR question_id IN (SELECT id FROM questions WHERE user_id = $1)
+            ",
+            user_id
+        )
+        .execute(&mut *tx)
+        .await
+        {
+            Ok(result) => result.rows_affected(),
+            Err(e) => return Err(Error::DbError(e)),
+        };
+
+        let questions = match sqlx::query!("DELETE FROM questions WHERE user_id = $1", user_id)
+            .execute(&mut *tx)
+            .await
+        {
+            Ok(result) => result.rows_affected(),
+            Err(e) => return Err(Error::DbError(e)),
+        };
+
+        match tx.commit().await {
+            Ok(_) => {
+                record_rows(attachments.len() + (answers + questions) as usize);
+                Ok((answers + questions, attachments))
+            }
+            Err(e) => Err(Error::DbError(e)),
+        }
+    }
+
+    /// Moves every question and answer of `from` to `to`. Returns the number
+    /// of rows moved.
+    #[instrument(name = "db.reassign_user_content", skip_all, fields(db.rows))]
+    pub async fn reassign_user_content(&self, from: Uuid, to: Uuid) -> Result<u64, Error> {
+        let mut tx = match self.conn.begin().await {
+            Ok(tx) => tx,
+            Err(e) => return Err(Error::DbError(e)),
+        };
+
+        let questions = match sqlx::query!(
+            "UPDATE questions SET user_id = $1 WHERE user_id = $2",
+            to,
+            from
+        )
+        .execute(&mut *tx)
+        .await
+        {
+            Ok(result) => result.rows_affected(),
+            Err(e) => return Err(Error::DbError(e)),
+        };
+
+        let answers = match sqlx::query!(
+            "UPDATE answers SET user_id = $1 WHERE user_id = $2",
+            to,
+            from
+        )
+        .execute(&mut *tx)
+        .await
+        {
+            Ok(result) => result.rows_affected(),
+            Err(e) => return Err(Error::DbError(e)),
+        };
+
+        match tx.commit().await {
+            Ok(_) => {
+                record_rows((questions + answers) as usize);
+                Ok(questions + answers)
+            }
+            Err(e) => Err(Error::DbError(e)),
+        }
+    }
+
+    #[instrument(name = "db.recent_questions", skip_all, fields(db.rows))]
+    pub async fn recent_questions(&self, limit: i64) -> Result<Vec<QuestionSummary>, Error> {
+        match sqlx::query_as!(
+            QuestionSummary,
+            r"
+            SELECT id, title, user_id, created_on FROM questions
+            ORDER BY created_on DESC
+            LIMIT $1
+            ",
+            limit
+        )
+        .fetch_all(&self.conn)
+        .await
+        {
+            Ok(questions) => {
+                record_rows(questions.len());
+                Ok(questions)
+            }
+            Err(e) => Err(Error::DbError(e)),
+        }
+    }
+}
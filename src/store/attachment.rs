@@ -0,0 +1,101 @@
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{
+    domain::attachment::{Attachment, AttachmentTarget},
+    error::Error,
+};
+
+use super::{record_rows, DbStore, UnitOfWork};
+
+impl DbStore {
+    #[instrument(name = "db.get_attachment", skip_all, fields(db.rows))]
+    pub async fn get_attachment(&self, id: Uuid) -> Result<Attachment, Error> {
+        match sqlx::query_as!(
+            Attachment,
+            r"
+            SELECT id, question_id, answer_id, filename, content_type, size, created_on
+            FROM attachments WHERE id = $1
+            ",
+            id
+        )
+        .fetch_one(&self.conn)
+        .await
+        {
+            Ok(attachment) => {
+                record_rows(1);
+                Ok(attachment)
+            }
+            Err(sqlx::Error::RowNotFound) => Err(Error::NotFound),
+            Err(e) => Err(Error::DbError(e)),
+        }
+    }
+
+    #[instrument(name = "db.get_attachments", skip_all, fields(db.rows))]
+    pub async fn get_attachments(
+        &self,
+        target: AttachmentTarget,
+    ) -> Result<Vec<Attachment>, Error> {
+        let (question_id, answer_id) = match target {
+            AttachmentTarget::Question(id) => (Some(id), None),
+            AttachmentTarget::Answer(id) => (None, Some(id)),
+        };
+
+        match sqlx::query_as!(
+            Attachment,
+            r"
+            SELECT id, question_id, answer_id, filename, content_type, size, created_on
+            FROM attachments
+            WHERE question_id = $1 OR answer_id = $2
+            ORDER BY created_on
+            ",
+            question_id,
+            answer_id
+        )
+        .fetch_all(&self.conn)
+        .await
+        {
+            Ok(attachments) => {
+                record_rows(attachments.len());
+                Ok(attachments)
+            }
+            Err(e) => Err(Error::DbError(e)),
+        }
+    }
+}
+
+impl UnitOfWork {
+    /// Records an attachment; lock the question or answer it belongs to first.
+    #[instrument(name = "db.add_attachment", skip_all, fields(db.rows))]
+    pub async fn add_attachment(
+        &mut self,
+        attachment: &Attachment,
+        user_id: Uuid,
+    ) -> Result<Attachment, Error> {
+        match sqlx::query_as!(
+            Attachment,
+            r"
+            INSERT INTO attachments
+                (id, question_id, answer_id, user_id, filename, content_type, size)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING id, question_id, answer_id, filename, content_type, size, created_on
+            ",
+            attachment.id,
+            attachment.question_id,
+            attachment.answer_id,
+            user_id,
+            attachment.filename,
+            attachment.content_type,
+            attachment.size
+        )
+        .fetch_one(&mut *self.tx)
+        .await
+        {
+            Ok(attachment) => {
+                record_rows(1);
+                Ok(attachment)
+            }
+            Err(e) => Err(Error::DbError(e)),
+        }
+    }
+}
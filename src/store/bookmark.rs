@@ -0,0 +1,133 @@
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{
+    domain::{
+        page::Cursor,
+        question::{Bookmark, CloseReason, Question, QuestionStatus},
+    },
+    error::Error,
+};
+
+use super::{record_rows, DbStore};
+
+impl DbStore {
+    /// Bookmarks the question; bookmarking it again keeps the original date.
+    #[instrument(name = "db.add_bookmark", skip_all, fields(db.rows))]
+    pub async fn add_bookmark(&self, user_id: Uuid, question_id: Uuid) -> Result<(), Error> {
+        match sqlx::query!(
+            r"
+            INSERT INTO bookmarks (user_id, question_id) VALUES ($1, $2)
+            ON CONFLICT DO NOTHING
+            ",
+            user_id,
+            question_id
+        )
+        .execute(&self.conn)
+        .await
+        {
+            Ok(result) => {
+                record_rows(result.rows_affected() as usize);
+                Ok(())
+            }
+            Err(sqlx::Error::Database(e)) if e.is_foreign_key_violation() => Err(Error::NotFound),
+            Err(e) => Err(Error::DbError(e)),
+        }
+    }
+
+    #[instrument(name = "db.delete_bookmark", skip_all, fields(db.rows))]
+    pub async fn delete_bookmark(&self, user_id: Uuid, question_id: Uuid) -> Result<(), Error> {
+        match sqlx::query!(
+            "DELETE FROM bookmarks WHERE user_id = $1 AND question_id = $2",
+            user_id,
+            question_id
+        )
+        .execute(&self.conn)
+        .await
+        {
+            Ok(result) => {
+                record_rows(result.rows_affected() as usize);
+                Ok(())
+            }
+            Err(e) => Err(Error::DbError(e)),
+        }
+    }
+
+    /// Which of `question_ids` the user has bookmarked.
+    #[instrument(name = "db.get_bookmarked", skip_all, fields(db.rows))]
+    pub async fn get_bookmarked(
+        &self,
+        user_id: Uuid,
+        question_ids: &[Uuid],
+    ) -> Result<Vec<Uuid>, Error> {
+        match sqlx::query_scalar!(
+            "SELECT question_id FROM bookmarks WHERE user_id = $1 AND question_id = ANY($2)",
+            user_id,
+            question_ids
+        )
+        .fetch_all(&self.conn)
+        .await
+        {
+            Ok(ids) => {
+                record_rows(ids.len());
+                Ok(ids)
+            }
+            Err(e) => Err(Error::DbError(e)),
+        }
+    }
+
+    /// The user's bookmarks, most recently bookmarked first. Returns up to
+    /// `limit` bookmarks after `before`.
+    #[instrument(name = "db.get_bookmarks", skip_all, fields(db.rows))]
+    pub async fn get_bookmarks(
+        &self,
+        user_id: Uuid,
+        before: Option<Cursor>,
+        limit: i64,
+    ) -> Result<Vec<Bookmark>, Error> {
+        match sqlx::query!(
+            r#"
+            SELECT q.id, q.title, q.content, q.content_html, q.tags, q.version,
+                q.status AS "status: QuestionStatus",
+                q.close_reason AS "close_reason: CloseReason", q.duplicate_of,
+                b.created_on AS bookmarked_on
+            FROM bookmarks b
+            JOIN questions q ON q.id = b.question_id
+            WHERE b.user_id = $1
+                AND ($2::timestamp IS NULL OR (b.created_on, q.id) < ($2, $3::uuid))
+            ORDER BY b.created_on DESC, q.id DESC
+            LIMIT $4
+            "#,
+            user_id,
+            before.map(|c| c.created_on),
+            before.map(|c| c.id),
+            limit
+        )
+        .fetch_all(&self.conn)
+        .await
+        {
+            Ok(rows) => {
+                record_rows(rows.len());
+                Ok(rows
+                    .into_iter()
+                    .map(|row| Bookmark {
+                        bookmarked_on: row.bookmarked_on,
+                        question: Question {
+                            id: row.id,
+                            title: row.title,
+                            content: row.content,
+                            content_html: row.content_html,
+                            tags: row.tags,
+                            version: row.version,
+                            status: row.status,
+                            close_reason: row.close_reason,
+                            duplicate_of: row.duplicate_of,
+                            bookmarked: Some(true),
+                        },
+                    })
+                    .collect())
+            }
+            Err(e) => Err(Error::DbError(e)),
+        }
+    }
+}
@@ -0,0 +1,57 @@
+use sqlx::{Postgres, Transaction};
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{
+    domain::{answer::Answer, event::Change},
+    error::Error,
+};
+
+use super::{record_rows, DbStore};
+
+/// Postgres channel that store mutations notify and every instance listens on.
+pub const EVENTS_CHANNEL: &str = "askly_events";
+
+impl DbStore {
+    #[instrument(name = "db.get_answer", skip_all, fields(db.rows))]
+    pub async fn get_answer(&self, id: Uuid) -> Result<Answer, Error> {
+        match sqlx::query_as!(
+            Answer,
+            r#"
+            SELECT id, content, content_html, question_id AS "question_id!", version
+            FROM answers WHERE id = $1
+            "#,
+            id
+        )
+        .fetch_one(&self.conn)
+        .await
+        {
+            Ok(answer) => {
+                record_rows(1);
+                Ok(answer)
+            }
+            Err(sqlx::Error::RowNotFound) => Err(Error::NotFound),
+            Err(e) => Err(Error::DbError(e)),
+        }
+    }
+}
+
+/// Queues `change` on [`EVENTS_CHANNEL`]. Postgres delivers it only if the
+/// transaction commits, so listeners never see a change that was rolled back.
+pub(super) async fn publish(
+    tx: &mut Transaction<'static, Postgres>,
+    change: &Change,
+) -> Result<(), Error> {
+    let payload = match serde_json::to_string(change) {
+        Ok(payload) => payload,
+        Err(_) => return Err(Error::ServerError),
+    };
+
+    match sqlx::query!("SELECT pg_notify($1, $2)", EVENTS_CHANNEL, payload)
+        .execute(&mut **tx)
+        .await
+    {
+        Ok(_) => Ok(()),
+        Err(e) => Err(Error::DbError(e)),
+    }
+}
@@ -0,0 +1,222 @@
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{
+    domain::{
+        feed::{FeedItem, FeedKind, Follows},
+        page::Cursor,
+    },
+    error::Error,
+};
+
+use super::{record_rows, DbStore};
+
+/// Maps a foreign key violation, i.e. following something that does not
+/// exist, to `NotFound`.
+fn follow_error(e: sqlx::Error) -> Error {
+    match e {
+        sqlx::Error::Database(ref db) if db.is_foreign_key_violation() => Error::NotFound,
+        e => Error::DbError(e),
+    }
+}
+
+impl DbStore {
+    #[instrument(name = "db.follow_question", skip_all, fields(db.rows))]
+    pub async fn follow_question(&self, user_id: Uuid, question_id: Uuid) -> Result<(), Error> {
+        match sqlx::query!(
+            r"
+            INSERT INTO question_follows (user_id, question_id) VALUES ($1, $2)
+            ON CONFLICT DO NOTHING
+            ",
+            user_id,
+            question_id
+        )
+        .execute(&self.conn)
+        .await
+        {
+            Ok(result) => {
+                record_rows(result.rows_affected() as usize);
+                Ok(())
+            }
+            Err(e) => Err(follow_error(e)),
+        }
+    }
+
+    #[instrument(name = "db.unfollow_question", skip_all, fields(db.rows))]
+    pub async fn unfollow_question(&self, user_id: Uuid, question_id: Uuid) -> Result<(), Error> {
+        match sqlx::query!(
+            "DELETE FROM question_follows WHERE user_id = $1 AND question_id = $2",
+            user_id,
+            question_id
+        )
+        .execute(&self.conn)
+        .await
+        {
+            Ok(result) => {
+                record_rows(result.rows_affected() as usize);
+                Ok(())
+            }
+            Err(e) => Err(Error::DbError(e)),
+        }
+    }
+
+    #[instrument(name = "db.follow_tag", skip_all, fields(db.rows))]
+    pub async fn follow_tag(&self, user_id: Uuid, tag: &str) -> Result<(), Error> {
+        match sqlx::query!(
+            r"
+            INSERT INTO tag_follows (user_id, tag) VALUES ($1, $2)
+            ON CONFLICT DO NOTHING
+            ",
+            user_id,
+            tag
+        )
+        .execute(&self.conn)
+        .await
+        {
+            Ok(result) => {
+                record_rows(result.rows_affected() as usize);
+                Ok(())
+            }
+            Err(e) => Err(follow_error(e)),
+        }
+    }
+
+    #[instrument(name = "db.unfollow_tag", skip_all, fields(db.rows))]
+    pub async fn unfollow_tag(&self, user_id: Uuid, tag: &str) -> Result<(), Error> {
+        match sqlx::query!(
+            "DELETE FROM tag_follows WHERE user_id = $1 AND tag = $2",
+            user_id,
+            tag
+        )
+        .execute(&self.conn)
+        .await
+        {
+            Ok(result) => {
+                record_rows(result.rows_affected() as usize);
+                Ok(())
+            }
+            Err(e) => Err(Error::DbError(e)),
+        }
+    }
+
+    #[instrument(name = "db.follow_user", skip_all, fields(db.rows))]
+    pub async fn follow_user(&self, user_id: Uuid, followed_id: Uuid) -> Result<(), Error> {
+        match sqlx::query!(
+            r"
+            INSERT INTO user_follows (user_id, followed_id) VALUES ($1, $2)
+            ON CONFLICT DO NOTHING
+            ",
+            user_id,
+            followed_id
+        )
+        .execute(&self.conn)
+        .await
+        {
+            Ok(result) => {
+                record_rows(result.rows_affected() as usize);
+                Ok(())
+            }
+            Err(e) => Err(follow_error(e)),
+        }
+    }
+
+    #[instrument(name = "db.unfollow_user", skip_all, fields(db.rows))]
+    pub async fn unfollow_user(&self, user_id: Uuid, followed_id: Uuid) -> Result<(), Error> {
+        match sqlx::query!(
+            "DELETE FROM user_follows WHERE user_id = $1 AND followed_id = $2",
+            user_id,
+            followed_id
+        )
+        .execute(&self.conn)
+        .await
+        {
+            Ok(result) => {
+                record_rows(result.rows_affected() as usize);
+                Ok(())
+            }
+            Err(e) => Err(Error::DbError(e)),
+        }
+    }
+
+    #[instrument(name = "db.get_follows", skip_all, fields(db.rows))]
+    pub async fn get_follows(&self, user_id: Uuid) -> Result<Follows, Error> {
+        match sqlx::query!(
+            r#"
+            SELECT
+                ARRAY(SELECT question_id FROM question_follows WHERE user_id = $1
+                    ORDER BY created_on) AS "questions!",
+                ARRAY(SELECT tag FROM tag_follows WHERE user_id = $1
+                    ORDER BY created_on) AS "tags!",
+                ARRAY(SELECT followed_id FROM user_follows WHERE user_id = $1
+                    ORDER BY created_on) AS "users!"
+            "#,
+            user_id
+        )
+        .fetch_one(&self.conn)
+        .await
+        {
+            Ok(row) => {
+                record_rows(row.questions.len() + row.tags.len() + row.users.len());
+                Ok(Follows {
+                    questions: row.questions,
+                    tags: row.tags,
+                    users: row.users,
+                })
+            }
+            Err(e) => Err(Error::DbError(e)),
+        }
+    }
+
+    /// New questions by followed users or with followed tags, and new answers
+    /// by followed users or to followed questions, newest first. The user's
+    /// own posts are left out. Returns up to `limit` items after `before`.
+    #[instrument(name = "db.get_feed", skip_all, fields(db.rows))]
+    pub async fn get_feed(
+        &self,
+        user_id: Uuid,
+        before: Option<Cursor>,
+        limit: i64,
+    ) -> Result<Vec<FeedItem>, Error> {
+        match sqlx::query_as!(
+            FeedItem,
+            r#"
+            SELECT kind AS "kind!: FeedKind", id AS "id!", question_id AS "question_id!",
+                question_title AS "question_title!", content_html AS "content_html!",
+                author_id, created_on AS "created_on!"
+            FROM (
+                SELECT 'question' AS kind, q.id, q.id AS question_id, q.title AS question_title,
+                    q.content_html, q.user_id AS author_id, q.created_on
+                FROM questions q
+                WHERE q.user_id IS DISTINCT FROM $1 AND (
+                    q.user_id IN (SELECT followed_id FROM user_follows WHERE user_id = $1)
+                    OR q.tags && ARRAY(SELECT tag FROM tag_follows WHERE user_id = $1)
+                )
+                UNION ALL
+                SELECT 'answer', a.id, q.id, q.title, a.content_html, a.user_id, a.created_on
+                FROM answers a
+                JOIN questions q ON q.id = a.question_id
+                WHERE a.user_id IS DISTINCT FROM $1 AND (
+                    a.user_id IN (SELECT followed_id FROM user_follows WHERE user_id = $1)
+                    OR a.question_id IN (SELECT question_id FROM question_follows WHERE user_id = $1)
+                )
+            ) feed
+            WHERE $2::timestamp IS NULL OR (created_on, id) < ($2, $3::uuid)
+            ORDER BY created_on DESC, id DESC
+            LIMIT $4
+            "#,
+            user_id,
+            before.map(|c| c.created_on),
+            before.map(|c| c.id),
+            limit
+        )
+        .fetch_all(&self.conn)
+        .await
+        {
+            Ok(items) => {
+                record_rows(items.len());
+                Ok(items)
+            }
+            Err(e) => Err(Error::DbError(e)),
+        }
+    }
+}
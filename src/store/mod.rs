@@ -0,0 +1,648 @@
+mod admin;
+mod attachment;
+mod bookmark;
+mod event;
+mod follow;
+mod notification;
+mod reputation;
+mod status;
+mod webhook;
+
+pub use event::EVENTS_CHANNEL;
+
+use rand::Rng;
+use sqlx::{migrate::Migrator, postgres::PgPoolOptions, PgPool, Postgres, Transaction};
+use tracing::{instrument, Span};
+use uuid::Uuid;
+
+use crate::{
+    domain::{
+        answer::Answer,
+        event::Change,
+        question::{CloseReason, Question, QuestionStatus},
+        reputation::BadgeKind,
+        user::{Credential, Role, User},
+        webhook::WebhookEvent,
+    },
+    error::Error,
+    markdown,
+};
+
+pub static MIGRATOR: Migrator = sqlx::migrate!();
+
+#[derive(Debug, Clone)]
+pub struct DbStore {
+    pub conn: PgPool,
+}
+
+impl DbStore {
+    pub async fn new(url: &str, max_connections: u32) -> Self {
+        let conn = PgPoolOptions::new()
+            .max_connections(max_connections)
+            .connect(url)
+            .await
+            .expect("Db connection failed");
+
+        Self { conn }
+    }
+
+    /// Starts a unit of work. Nothing done through it is visible to other
+    /// connections until [`UnitOfWork::commit`] is called; dropping it rolls back.
+    pub async fn begin(&self) -> Result<UnitOfWork, Error> {
+        match self.conn.begin().await {
+            Ok(tx) => Ok(UnitOfWork { tx }),
+            Err(e) => Err(Error::DbError(e)),
+        }
+    }
+
+    /// Round-trips to the database and returns the versions of embedded
+    /// migrations that have not been applied yet.
+    #[instrument(name = "db.pending_migrations", skip_all, fields(db.rows))]
+    pub async fn pending_migrations(&self) -> Result<Vec<i64>, Error> {
+        let applied: Vec<i64> =
+            match sqlx::query_scalar("SELECT version FROM _sqlx_migrations WHERE success")
+                .fetch_all(&self.conn)
+                .await
+            {
+                Ok(applied) => applied,
+                Err(e) => return Err(Error::DbError(e)),
+            };
+
+        Ok(MIGRATOR
+            .iter()
+            .filter(|m| m.migration_type.is_up_migration() && !applied.contains(&m.version))
+            .map(|m| m.version)
+            .collect())
+    }
+
+    #[instrument(name = "db.get_questions", skip_all, fields(db.rows))]
+    pub async fn get_questions(&self) -> Result<Vec<Question>, Error> {
+        match sqlx::query_as!(
+            Question,
+            r#"
+            SELECT id, title, content, content_html, tags, version,
+                status AS "status: QuestionStatus", close_reason AS "close_reason: CloseReason",
+                duplicate_of, NULL::bool AS bookmarked
+            FROM questions
+            "#
+        )
+        .fetch_all(&self.conn)
+        .await
+        {
+            Ok(questions) => {
+                record_rows(questions.len());
+                Ok(questions)
+            }
+            Err(e) => Err(Error::DbError(e)),
+        }
+    }
+
+    #[instrument(name = "db.get_question", skip_all, fields(db.rows))]
+    pub async fn get_question(&self, id: Uuid) -> Result<Question, Error> {
+        match sqlx::query_as!(
+            Question,
+            r#"
+            SELECT id, title, content, content_html, tags, version,
+                status AS "status: QuestionStatus", close_reason AS "close_reason: CloseReason",
+                duplicate_of, NULL::bool AS bookmarked
+            FROM questions WHERE id = $1
+            "#,
+            id
+        )
+        .fetch_one(&self.conn)
+        .await
+        {
+            Ok(question) => {
+                record_rows(1);
+                Ok(question)
+            }
+            Err(sqlx::Error::RowNotFound) => Err(Error::NotFound),
+            Err(e) => Err(Error::DbError(e)),
+        }
+    }
+
+    /// The question's author, if it recorded one.
+    #[instrument(name = "db.get_question_author", skip_all, fields(db.rows))]
+    pub async fn get_question_author(&self, id: Uuid) -> Result<Option<Uuid>, Error> {
+        match sqlx::query_scalar!("SELECT user_id FROM questions WHERE id = $1", id)
+            .fetch_one(&self.conn)
+            .await
+        {
+            Ok(author_id) => {
+                record_rows(1);
+                Ok(author_id)
+            }
+            Err(sqlx::Error::RowNotFound) => Err(Error::NotFound),
+            Err(e) => Err(Error::DbError(e)),
+        }
+    }
+
+    /// Stores the question and queues its webhook deliveries in one transaction.
+    #[instrument(name = "db.add_question", skip_all, fields(db.rows))]
+    pub async fn add_question(&self, question: &Question, user_id: Uuid) -> Result<(), Error> {
+        let mut tx = match self.conn.begin().await {
+            Ok(tx) => tx,
+            Err(e) => return Err(Error::DbError(e)),
+        };
+
+        if let Err(e) = sqlx::query!(
+            r"
+            INSERT INTO questions (id, title, content, content_html, tags, user_id)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ",
+            question.id,
+            question.title,
+            question.content,
+            question.content_html,
+            question.tags.as_deref(),
+            user_id
+        )
+        .execute(&mut *tx)
+        .await
+        {
+            return Err(Error::DbError(e));
+        }
+
+        webhook::enqueue(&mut tx, WebhookEvent::QuestionCreated, question).await?;
+
+        match tx.commit().await {
+            Ok(_) => {
+                record_rows(1);
+                Ok(())
+            }
+            Err(e) => Err(Error::DbError(e)),
+        }
+    }
+
+    /// Which of `tags` no question uses yet.
+    #[instrument(name = "db.get_new_tags", skip_all, fields(db.rows))]
+    pub async fn get_new_tags(&self, tags: &[String]) -> Result<Vec<String>, Error> {
+        match sqlx::query_scalar!(
+            r#"
+            SELECT tag AS "tag!" FROM unnest($1::text[]) AS tag
+            WHERE NOT EXISTS (SELECT 1 FROM questions WHERE tag = ANY(tags))
+            "#,
+            tags
+        )
+        .fetch_all(&self.conn)
+        .await
+        {
+            Ok(tags) => {
+                record_rows(tags.len());
+                Ok(tags)
+            }
+            Err(e) => Err(Error::DbError(e)),
+        }
+    }
+
+    /// Stores the answer if the question is open, notifies the question's
+    /// author, awards a first answer badge, publishes the change and queues
+    /// webhook deliveries in one transaction.
+    #[instrument(name = "db.add_answer", skip_all, fields(db.rows))]
+    pub async fn add_answer(&self, input: Answer, user_id: Uuid) -> Result<Answer, Error> {
+        let mut tx = match self.conn.begin().await {
+            Ok(tx) => tx,
+            Err(e) => return Err(Error::DbError(e)),
+        };
+
+        // Shares the lock a status change takes, so an answer can't slip in
+        // while the question is being closed.
+        match sqlx::query_scalar!(
+            r#"SELECT status AS "status: QuestionStatus" FROM questions WHERE id = $1 FOR SHARE"#,
+            input.question_id
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        {
+            Ok(None) => return Err(Error::NotFound),
+            Ok(Some(QuestionStatus::Open)) => {}
+            Ok(Some(status)) => return Err(Error::QuestionNotOpen(status)),
+            Err(e) => return Err(Error::DbError(e)),
+        }
+
+        let answer = match sqlx::query_as!(
+            Answer,
+            r#"
+            INSERT INTO answers (id, content, content_html, question_id, user_id)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING id, content, content_html, question_id AS "question_id!", version
+            "#,
+            input.id,
+            input.content,
+            input.content_html,
+            input.question_id,
+            user_id
+        )
+        .fetch_one(&mut *tx)
+        .await
+        {
+            Ok(answer) => answer,
+            Err(e) => return Err(Error::DbError(e)),
+        };
+
+        notification::notify_answer(&mut tx, &answer, user_id).await?;
+        reputation::award_badge(&mut tx, user_id, BadgeKind::FirstAnswer).await?;
+        event::publish(
+            &mut tx,
+            &Change::AnswerAdded {
+                question_id: answer.question_id,
+                answer_id: answer.id,
+            },
+        )
+        .await?;
+        webhook::enqueue(&mut tx, WebhookEvent::AnswerCreated, &answer).await?;
+
+        match tx.commit().await {
+            Ok(_) => {
+                record_rows(1);
+                Ok(answer)
+            }
+            Err(e) => Err(Error::DbError(e)),
+        }
+    }
+
+    #[instrument(name = "db.add_user", skip_all, fields(db.rows))]
+    pub async fn add_user(&self, input: User) -> Result<(), Error> {
+        match sqlx::query!(
+            r"
+            INSERT INTO users (id, name, email, password, role)
+            VALUES ($1, $2, $3, $4, $5)
+            ",
+            input.id,
+            input.name,
+            input.email,
+            input.password,
+            input.role as Role
+        )
+        .execute(&self.conn)
+        .await
+        {
+            Ok(result) => {
+                record_rows(result.rows_affected() as usize);
+                Ok(())
+            }
+            Err(e) => Err(Error::DbError(e)),
+        }
+    }
+
+    /// Renders `content_html` for rows stored before it existed. Returns the
+    /// number of questions and answers updated.
+    #[instrument(name = "db.render_missing_html", skip_all, fields(db.rows))]
+    pub async fn render_missing_html(&self) -> Result<u64, Error> {
+        let mut tx = match self.conn.begin().await {
+            Ok(tx) => tx,
+            Err(e) => return Err(Error::DbError(e)),
+        };
+
+        let questions = match sqlx::query!(
+            "SELECT id, content FROM questions WHERE content_html = '' AND content <> ''"
+        )
+        .fetch_all(&mut *tx)
+        .await
+        {
+            Ok(rows) => rows,
+            Err(e) => return Err(Error::DbError(e)),
+        };
+
+        for row in &questions {
+            if let Err(e) = sqlx::query!(
+                "UPDATE questions SET content_html = $1 WHERE id = $2",
+                markdown::render(&row.content),
+                row.id
+            )
+            .execute(&mut *tx)
+            .await
+            {
+                return Err(Error::DbError(e));
+            }
+        }
+
+        let answers = match sqlx::query!(
+            "SELECT id, content FROM answers WHERE content_html = '' AND content <> ''"
+        )
+        .fetch_all(&mut *tx)
+        .await
+        {
+            Ok(rows) => rows,
+            Err(e) => return Err(Error::DbError(e)),
+        };
+
+        for row in &answers {
+            if let Err(e) = sqlx::query!(
+                "UPDATE answers SET content_html = $1 WHERE id = $2",
+                markdown::render(&row.content),
+                row.id
+            )
+            .execute(&mut *tx)
+            .await
+            {
+                return Err(Error::DbError(e));
+            }
+        }
+
+        let updated = (questions.len() + answers.len()) as u64;
+        match tx.commit().await {
+            Ok(_) => {
+                record_rows(updated as usize);
+                Ok(updated)
+            }
+            Err(e) => Err(Error::DbError(e)),
+        }
+    }
+
+    #[instrument(name = "db.is_user_disabled", skip_all, fields(db.rows))]
+    pub async fn is_user_disabled(&self, user_id: Uuid) -> Result<bool, Error> {
+        match sqlx::query_scalar!("SELECT disabled FROM users WHERE id = $1", user_id)
+            .fetch_one(&self.conn)
+            .await
+        {
+            Ok(disabled) => {
+                record_rows(1);
+                Ok(disabled)
+            }
+            Err(sqlx::Error::RowNotFound) => Err(Error::NotFound),
+            Err(e) => Err(Error::DbError(e)),
+        }
+    }
+
+    #[instrument(name = "db.get_user_role", skip_all, fields(db.rows))]
+    pub async fn get_user_role(&self, user_id: Uuid) -> Result<Role, Error> {
+        match sqlx::query_scalar!(
+            r#"SELECT role AS "role: Role" FROM users WHERE id = $1"#,
+            user_id
+        )
+        .fetch_one(&self.conn)
+        .await
+        {
+            Ok(role) => {
+                record_rows(1);
+                Ok(role)
+            }
+            Err(sqlx::Error::RowNotFound) => Err(Error::NotFound),
+            Err(e) => Err(Error::DbError(e)),
+        }
+    }
+
+    #[instrument(name = "db.find_user_by_credential", skip_all, fields(db.rows))]
+    pub async fn find_user_by_credential(&self, credential: Credential) -> Result<User, Error> {
+        let user = sqlx::query_as!(
+            User,
+            r#"
+            SELECT id, name, email, password, role AS "role: Role", disabled
+            FROM users WHERE email = $1
+            "#,
+            credential.email
+        )
+        .fetch_one(&self.conn)
+        .await
+        .map_err(|_| Error::InvalidEmailPassword)?;
+        record_rows(1);
+
+        match verify_password(&user.password, credential.password.as_bytes()) {
+            Ok(verified) => {
+                if !verified {
+                    Err(Error::InvalidEmailPassword)
+                } else if user.disabled {
+                    Err(Error::AccountDisabled)
+                } else {
+                    Ok(user)
+                }
+            }
+
+            Err(_) => Err(Error::ServerError),
+        }
+    }
+}
+
+/// Transaction-scoped handle for check-and-mutate sequences.
+///
+/// Ownership checks lock the row (`SELECT ... FOR UPDATE`) so that the
+/// mutation that follows sees the same row the check did.
+pub struct UnitOfWork {
+    tx: Transaction<'static, Postgres>,
+}
+
+impl UnitOfWork {
+    pub async fn commit(self) -> Result<(), Error> {
+        match self.tx.commit().await {
+            Ok(_) => Ok(()),
+            Err(e) => Err(Error::DbError(e)),
+        }
+    }
+
+    /// Locks the question, checks that it belongs to `user_id` and returns its
+    /// current version.
+    #[instrument(name = "db.lock_question", skip_all, fields(db.rows))]
+    pub async fn lock_question(&mut self, id: Uuid, user_id: Uuid) -> Result<i32, Error> {
+        match sqlx::query!(
+            "SELECT user_id, version FROM questions WHERE id = $1 FOR UPDATE",
+            id
+        )
+        .fetch_optional(&mut *self.tx)
+        .await
+        {
+            Ok(None) => Err(Error::NotFound),
+            Ok(Some(row)) if row.user_id != Some(user_id) => Err(Error::NotOwner),
+            Ok(Some(row)) => {
+                record_rows(1);
+                Ok(row.version)
+            }
+            Err(e) => Err(Error::DbError(e)),
+        }
+    }
+
+    #[instrument(name = "db.update_question", skip_all, fields(db.rows))]
+    pub async fn update_question(&mut self, question: Question) -> Result<Question, Error> {
+        let question = match sqlx::query_as!(
+            Question,
+            r#"
+            UPDATE questions
+            SET title = $1, content = $2, content_html = $3, tags = $4, version = version + 1
+            WHERE id = $5
+            RETURNING id, title, content, content_html, tags, version,
+                status AS "status: QuestionStatus", close_reason AS "close_reason: CloseReason",
+                duplicate_of, NULL::bool AS bookmarked
+            "#,
+            question.title,
+            question.content,
+            question.content_html,
+            question.tags.as_deref(),
+            question.id
+        )
+        .fetch_one(&mut *self.tx)
+        .await
+        {
+            Ok(question) => question,
+            Err(sqlx::Error::RowNotFound) => return Err(Error::NotFound),
+            Err(e) => return Err(Error::DbError(e)),
+        };
+
+        event::publish(
+            &mut self.tx,
+            &Change::QuestionUpdated {
+                question_id: question.id,
+            },
+        )
+        .await?;
+        webhook::enqueue(&mut self.tx, WebhookEvent::QuestionUpdated, &question).await?;
+
+        record_rows(1);
+        Ok(question)
+    }
+
+    /// Deletes the question together with its answers and their attachments.
+    /// Returns the ids of the deleted attachments, whose bodies are left for
+    /// the caller to remove from storage once committed.
+    #[instrument(name = "db.delete_question", skip_all, fields(db.rows))]
+    pub async fn delete_question(&mut self, id: Uuid) -> Result<Vec<Uuid>, Error> {
+        let attachments = match sqlx::query_scalar!(
+            r"
+            DELETE FROM attachments
+            WHERE question_id = $1
+                OR answer_id IN (SELECT id FROM answers WHERE question_id = $1)
+            RETURNING id
+            ",
+            id
+        )
+        .fetch_all(&mut *self.tx)
+        .await
+        {
+            Ok(ids) => ids,
+            Err(e) => return Err(Error::DbError(e)),
+        };
+
+        if let Err(e) = sqlx::query!("DELETE FROM answers WHERE question_id = $1", id)
+            .execute(&mut *self.tx)
+            .await
+        {
+            return Err(Error::DbError(e));
+        }
+
+        let deleted = match sqlx::query!("DELETE FROM questions WHERE id = $1", id)
+            .execute(&mut *self.tx)
+            .await
+        {
+            Ok(result) => result.rows_affected(),
+            Err(e) => return Err(Error::DbError(e)),
+        };
+
+        event::publish(&mut self.tx, &Change::QuestionDeleted { question_id: id }).await?;
+        webhook::enqueue(
+            &mut self.tx,
+            WebhookEvent::QuestionDeleted,
+            &serde_json::json!({ "id": id }),
+        )
+        .await?;
+
+        record_rows(deleted as usize);
+        Ok(attachments)
+    }
+
+    /// Locks the question and returns its author, current version and
+    /// status, for callers that let others than the author through.
+    #[instrument(name = "db.lock_question_author", skip_all, fields(db.rows))]
+    pub async fn lock_question_author(
+        &mut self,
+        id: Uuid,
+    ) -> Result<(Option<Uuid>, i32, QuestionStatus), Error> {
+        match sqlx::query!(
+            r#"
+            SELECT user_id, version, status AS "status: QuestionStatus"
+            FROM questions WHERE id = $1 FOR UPDATE
+            "#,
+            id
+        )
+        .fetch_optional(&mut *self.tx)
+        .await
+        {
+            Ok(None) => Err(Error::NotFound),
+            Ok(Some(row)) => {
+                record_rows(1);
+                Ok((row.user_id, row.version, row.status))
+            }
+            Err(e) => Err(Error::DbError(e)),
+        }
+    }
+
+    /// Locks the answer, checks that it belongs to `user_id` and returns its
+    /// current version.
+    #[instrument(name = "db.lock_answer", skip_all, fields(db.rows))]
+    pub async fn lock_answer(&mut self, id: Uuid, user_id: Uuid) -> Result<i32, Error> {
+        match sqlx::query!(
+            "SELECT user_id, version FROM answers WHERE id = $1 FOR UPDATE",
+            id
+        )
+        .fetch_optional(&mut *self.tx)
+        .await
+        {
+            Ok(None) => Err(Error::NotFound),
+            Ok(Some(row)) if row.user_id != Some(user_id) => Err(Error::NotOwner),
+            Ok(Some(row)) => {
+                record_rows(1);
+                Ok(row.version)
+            }
+            Err(e) => Err(Error::DbError(e)),
+        }
+    }
+
+    /// Deletes the answer and its attachments, returning the attachment ids
+    /// like `delete_question`.
+    #[instrument(name = "db.delete_answer", skip_all, fields(db.rows))]
+    pub async fn delete_answer(&mut self, id: Uuid) -> Result<Vec<Uuid>, Error> {
+        let attachments = match sqlx::query_scalar!(
+            "DELETE FROM attachments WHERE answer_id = $1 RETURNING id",
+            id
+        )
+        .fetch_all(&mut *self.tx)
+        .await
+        {
+            Ok(ids) => ids,
+            Err(e) => return Err(Error::DbError(e)),
+        };
+
+        let question_id = match sqlx::query_scalar!(
+            r#"DELETE FROM answers WHERE id = $1 RETURNING question_id AS "question_id!""#,
+            id
+        )
+        .fetch_one(&mut *self.tx)
+        .await
+        {
+            Ok(question_id) => question_id,
+            Err(sqlx::Error::RowNotFound) => return Err(Error::NotFound),
+            Err(e) => return Err(Error::DbError(e)),
+        };
+
+        event::publish(
+            &mut self.tx,
+            &Change::AnswerDeleted {
+                question_id,
+                answer_id: id,
+            },
+        )
+        .await?;
+        webhook::enqueue(
+            &mut self.tx,
+            WebhookEvent::AnswerDeleted,
+            &serde_json::json!({ "id": id, "question_id": question_id }),
+        )
+        .await?;
+
+        record_rows(1);
+        Ok(attachments)
+    }
+}
+
+/// Records how many rows a statement returned or affected on its span.
+fn record_rows(rows: usize) {
+    Span::current().record("db.rows", rows);
+}
+
+pub fn hash_password(password: &[u8]) -> String {
+    let salt = rand::thread_rng().gen::<[u8; 32]>();
+    let config = argon2::Config::default();
+    argon2::hash_encoded(password, &salt, &config).unwrap()
+}
+
+fn verify_password(hash: &str, password: &[u8]) -> Result<bool, argon2::Error> {
+    argon2::verify_encoded(hash, password)
+}
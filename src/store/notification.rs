@@ -0,0 +1,198 @@
+use sqlx::{Postgres, Transaction};
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{
+    domain::{
+        answer::Answer,
+        notification::{Notification, NotificationKind, NotificationPreferences},
+    },
+    error::Error,
+};
+
+use super::{record_rows, DbStore};
+
+impl DbStore {
+    #[instrument(name = "db.get_notifications", skip_all, fields(db.rows))]
+    pub async fn get_notifications(
+        &self,
+        user_id: Uuid,
+        unread_only: bool,
+        limit: i64,
+    ) -> Result<Vec<Notification>, Error> {
+        match sqlx::query_as!(
+            Notification,
+            r#"
+            SELECT id, kind AS "kind: NotificationKind", question_id, answer_id, actor_id,
+                read_on IS NOT NULL AS "read!", created_on
+            FROM notifications
+            WHERE user_id = $1 AND (NOT $2 OR read_on IS NULL)
+            ORDER BY created_on DESC
+            LIMIT $3
+            "#,
+            user_id,
+            unread_only,
+            limit
+        )
+        .fetch_all(&self.conn)
+        .await
+        {
+            Ok(notifications) => {
+                record_rows(notifications.len());
+                Ok(notifications)
+            }
+            Err(e) => Err(Error::DbError(e)),
+        }
+    }
+
+    #[instrument(name = "db.count_unread_notifications", skip_all, fields(db.rows))]
+    pub async fn count_unread_notifications(&self, user_id: Uuid) -> Result<i64, Error> {
+        match sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) AS "count!" FROM notifications
+            WHERE user_id = $1 AND read_on IS NULL
+            "#,
+            user_id
+        )
+        .fetch_one(&self.conn)
+        .await
+        {
+            Ok(count) => {
+                record_rows(1);
+                Ok(count)
+            }
+            Err(e) => Err(Error::DbError(e)),
+        }
+    }
+
+    /// Marks the given notifications, or all of them when `ids` is `None`, as
+    /// read. Ids belonging to other users are ignored.
+    #[instrument(name = "db.mark_notifications_read", skip_all, fields(db.rows))]
+    pub async fn mark_notifications_read(
+        &self,
+        user_id: Uuid,
+        ids: Option<&[Uuid]>,
+    ) -> Result<u64, Error> {
+        match sqlx::query!(
+            r"
+            UPDATE notifications SET read_on = NOW()
+            WHERE user_id = $1 AND read_on IS NULL AND ($2::uuid[] IS NULL OR id = ANY($2))
+            ",
+            user_id,
+            ids
+        )
+        .execute(&self.conn)
+        .await
+        {
+            Ok(result) => {
+                record_rows(result.rows_affected() as usize);
+                Ok(result.rows_affected())
+            }
+            Err(e) => Err(Error::DbError(e)),
+        }
+    }
+
+    #[instrument(name = "db.get_notification_preferences", skip_all, fields(db.rows))]
+    pub async fn get_notification_preferences(
+        &self,
+        user_id: Uuid,
+    ) -> Result<NotificationPreferences, Error> {
+        match sqlx::query_as!(
+            NotificationPreferences,
+            "SELECT answer, accepted FROM notification_preferences WHERE user_id = $1",
+            user_id
+        )
+        .fetch_optional(&self.conn)
+        .await
+        {
+            Ok(preferences) => {
+                record_rows(preferences.iter().len());
+                Ok(preferences.unwrap_or_default())
+            }
+            Err(e) => Err(Error::DbError(e)),
+        }
+    }
+
+    #[instrument(name = "db.set_notification_preferences", skip_all, fields(db.rows))]
+    pub async fn set_notification_preferences(
+        &self,
+        user_id: Uuid,
+        preferences: &NotificationPreferences,
+    ) -> Result<(), Error> {
+        match sqlx::query!(
+            r"
+            INSERT INTO notification_preferences (user_id, answer, accepted) VALUES ($1, $2, $3)
+            ON CONFLICT (user_id)
+            DO UPDATE SET answer = EXCLUDED.answer, accepted = EXCLUDED.accepted
+            ",
+            user_id,
+            preferences.answer,
+            preferences.accepted
+        )
+        .execute(&self.conn)
+        .await
+        {
+            Ok(result) => {
+                record_rows(result.rows_affected() as usize);
+                Ok(())
+            }
+            Err(e) => Err(Error::DbError(e)),
+        }
+    }
+}
+
+/// Tells the question's author about a new answer, unless they wrote it
+/// themselves or turned answer notifications off.
+pub(super) async fn notify_answer(
+    tx: &mut Transaction<'static, Postgres>,
+    answer: &Answer,
+    actor_id: Uuid,
+) -> Result<(), Error> {
+    match sqlx::query!(
+        r"
+        INSERT INTO notifications (id, user_id, kind, question_id, answer_id, actor_id)
+        SELECT $1, q.user_id, 'answer', q.id, $2, $3
+        FROM questions q
+        LEFT JOIN notification_preferences p ON p.user_id = q.user_id
+        WHERE q.id = $4 AND q.user_id <> $3 AND COALESCE(p.answer, TRUE)
+        ",
+        Uuid::new_v4(),
+        answer.id,
+        actor_id,
+        answer.question_id
+    )
+    .execute(&mut **tx)
+    .await
+    {
+        Ok(_) => Ok(()),
+        Err(e) => Err(Error::DbError(e)),
+    }
+}
+
+/// Tells the answer's author that it was accepted, unless they accepted it
+/// themselves or turned accept notifications off.
+pub(super) async fn notify_accepted(
+    tx: &mut Transaction<'static, Postgres>,
+    answer: &Answer,
+    actor_id: Uuid,
+) -> Result<(), Error> {
+    match sqlx::query!(
+        r"
+        INSERT INTO notifications (id, user_id, kind, question_id, answer_id, actor_id)
+        SELECT $1, a.user_id, 'accepted', $2, a.id, $3
+        FROM answers a
+        LEFT JOIN notification_preferences p ON p.user_id = a.user_id
+        WHERE a.id = $4 AND a.user_id <> $3 AND COALESCE(p.accepted, TRUE)
+        ",
+        Uuid::new_v4(),
+        answer.question_id,
+        actor_id,
+        answer.id
+    )
+    .execute(&mut **tx)
+    .await
+    {
+        Ok(_) => Ok(()),
+        Err(e) => Err(Error::DbError(e)),
+    }
+}
@@ -0,0 +1,801 @@
+use sqlx::{PgExecutor, Postgres, Transaction};
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{
+    config::ReputationConfig,
+    domain::{
+        answer::{Answer, AnswerView},
+        event::Change,
+        page::Cursor,
+        reputation::{
+            AuthorSummary, Badge, BadgeKind, ReputationEvent, ReputationReason, UserProfile,
+            VoteResult, VoteTarget,
+        },
+        webhook::WebhookEvent,
+    },
+    error::Error,
+};
+
+use super::{event, notification, record_rows, webhook, DbStore};
+
+/// Accepted answers needed for [`BadgeKind::TenAccepted`].
+const TEN_ACCEPTED: i64 = 10;
+
+impl VoteTarget {
+    /// `(question_id, answer_id)` as stored in `votes` and `reputation_events`.
+    fn columns(self) -> (Option<Uuid>, Option<Uuid>) {
+        match self {
+            VoteTarget::Question(id) => (Some(id), None),
+            VoteTarget::Answer(id) => (None, Some(id)),
+        }
+    }
+}
+
+impl DbStore {
+    /// Records the caller's vote, replacing any earlier one, and credits or
+    /// debits the post's author in the same transaction.
+    #[instrument(name = "db.vote", skip_all, fields(db.rows))]
+    pub async fn vote(
+        &self,
+        user_id: Uuid,
+        target: VoteTarget,
+        value: i16,
+        points: &ReputationConfig,
+    ) -> Result<VoteResult, Error> {
+        // There is no row to lock before a user's first vote, so two of them
+        // at once both insert. The loser hits the unique index and runs again,
+        // now seeing the winner's vote.
+        match self.try_vote(user_id, target, value, points).await {
+            Err(Error::DbError(sqlx::Error::Database(e))) if e.is_unique_violation() => {
+                self.try_vote(user_id, target, value, points).await
+            }
+            result => result,
+        }
+    }
+
+    async fn try_vote(
+        &self,
+        user_id: Uuid,
+        target: VoteTarget,
+        value: i16,
+        points: &ReputationConfig,
+    ) -> Result<VoteResult, Error> {
+        let mut tx = match self.conn.begin().await {
+            Ok(tx) => tx,
+            Err(e) => return Err(Error::DbError(e)),
+        };
+
+        let (author_id, question_id) = post(&mut tx, target).await?;
+        if author_id == Some(user_id) {
+            return Err(Error::InvalidInput(
+                "you cannot vote on your own post".to_string(),
+            ));
+        }
+
+        if current_vote(&mut tx, user_id, target).await? != Some(value) {
+            remove_vote(&mut tx, user_id, target, author_id).await?;
+
+            let (question_id, answer_id) = target.columns();
+            if let Err(e) = sqlx::query!(
+                "INSERT INTO votes (user_id, question_id, answer_id, value) VALUES ($1, $2, $3, $4)",
+                user_id,
+                question_id,
+                answer_id,
+                value
+            )
+            .execute(&mut *tx)
+            .await
+            {
+                return Err(Error::DbError(e));
+            }
+
+            if let Some(author_id) = author_id {
+                let (delta, reason) = if value > 0 {
+                    let upvote = match target {
+                        VoteTarget::Question(_) => points.question_upvote,
+                        VoteTarget::Answer(_) => points.answer_upvote,
+                    };
+                    let earned = upvotes_today(&mut tx, author_id).await?;
+                    let left = (i64::from(points.daily_cap) - earned).max(0);
+                    (upvote.min(left as i32), ReputationReason::Upvote)
+                } else {
+                    (-points.downvote, ReputationReason::Downvote)
+                };
+
+                let (question_id, answer_id) = target.columns();
+                record(
+                    &mut tx,
+                    author_id,
+                    delta,
+                    reason,
+                    user_id,
+                    question_id,
+                    answer_id,
+                )
+                .await?;
+            }
+        }
+
+        let score = score(&mut *tx, target).await?;
+        publish_vote(&mut tx, question_id, target).await?;
+
+        match tx.commit().await {
+            Ok(_) => {
+                record_rows(1);
+                Ok(VoteResult {
+                    score,
+                    vote: Some(value),
+                })
+            }
+            Err(e) => Err(Error::DbError(e)),
+        }
+    }
+
+    /// Retracts the caller's vote, if any, and cancels its reputation.
+    #[instrument(name = "db.unvote", skip_all, fields(db.rows))]
+    pub async fn unvote(&self, user_id: Uuid, target: VoteTarget) -> Result<VoteResult, Error> {
+        let mut tx = match self.conn.begin().await {
+            Ok(tx) => tx,
+            Err(e) => return Err(Error::DbError(e)),
+        };
+
+        let (author_id, question_id) = post(&mut tx, target).await?;
+        remove_vote(&mut tx, user_id, target, author_id).await?;
+        let score = score(&mut *tx, target).await?;
+        publish_vote(&mut tx, question_id, target).await?;
+
+        match tx.commit().await {
+            Ok(_) => {
+                record_rows(1);
+                Ok(VoteResult { score, vote: None })
+            }
+            Err(e) => Err(Error::DbError(e)),
+        }
+    }
+
+    /// Marks the answer as its question's accepted answer. Only the
+    /// question's author may accept; accepting another answer moves the
+    /// reputation over to that answer's author.
+    #[instrument(name = "db.accept_answer", skip_all, fields(db.rows))]
+    pub async fn accept_answer(
+        &self,
+        user_id: Uuid,
+        answer_id: Uuid,
+        points: &ReputationConfig,
+    ) -> Result<Answer, Error> {
+        let mut tx = match self.conn.begin().await {
+            Ok(tx) => tx,
+            Err(e) => return Err(Error::DbError(e)),
+        };
+
+        let row = match sqlx::query!(
+            r#"
+            SELECT a.user_id AS author_id, q.id AS question_id, q.user_id AS owner_id,
+                q.accepted_answer_id
+            FROM answers a
+            JOIN questions q ON q.id = a.question_id
+            WHERE a.id = $1
+            FOR UPDATE OF q
+            "#,
+            answer_id
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        {
+            Ok(Some(row)) => row,
+            Ok(None) => return Err(Error::NotFound),
+            Err(e) => return Err(Error::DbError(e)),
+        };
+
+        if row.owner_id != Some(user_id) {
+            return Err(Error::NotOwner);
+        }
+
+        let answer = match sqlx::query_as!(
+            Answer,
+            r#"
+            SELECT id, content, content_html, question_id AS "question_id!", version
+            FROM answers WHERE id = $1
+            "#,
+            answer_id
+        )
+        .fetch_one(&mut *tx)
+        .await
+        {
+            Ok(answer) => answer,
+            Err(e) => return Err(Error::DbError(e)),
+        };
+
+        if row.accepted_answer_id == Some(answer_id) {
+            return Ok(answer);
+        }
+
+        if let Some(previous) = row.accepted_answer_id {
+            undo_accept(&mut tx, previous).await?;
+        }
+
+        if let Err(e) = sqlx::query!(
+            "UPDATE questions SET accepted_answer_id = $1 WHERE id = $2",
+            answer_id,
+            row.question_id
+        )
+        .execute(&mut *tx)
+        .await
+        {
+            return Err(Error::DbError(e));
+        }
+
+        // Accepting your own answer settles the question but earns nothing.
+        if let Some(author_id) = row.author_id.filter(|&author_id| author_id != user_id) {
+            record(
+                &mut tx,
+                author_id,
+                points.accepted,
+                ReputationReason::Accepted,
+                user_id,
+                Some(row.question_id),
+                Some(answer_id),
+            )
+            .await?;
+
+            if accepted_count(&mut tx, author_id).await? >= TEN_ACCEPTED {
+                award_badge(&mut tx, author_id, BadgeKind::TenAccepted).await?;
+            }
+        }
+
+        notification::notify_accepted(&mut tx, &answer, user_id).await?;
+        event::publish(
+            &mut tx,
+            &Change::AnswerAccepted {
+                question_id: row.question_id,
+                answer_id,
+            },
+        )
+        .await?;
+        webhook::enqueue(&mut tx, WebhookEvent::AnswerAccepted, &answer).await?;
+
+        match tx.commit().await {
+            Ok(_) => {
+                record_rows(1);
+                Ok(answer)
+            }
+            Err(e) => Err(Error::DbError(e)),
+        }
+    }
+
+    /// Clears the question's accepted answer and cancels its reputation.
+    #[instrument(name = "db.unaccept_answer", skip_all, fields(db.rows))]
+    pub async fn unaccept_answer(&self, user_id: Uuid, answer_id: Uuid) -> Result<(), Error> {
+        let mut tx = match self.conn.begin().await {
+            Ok(tx) => tx,
+            Err(e) => return Err(Error::DbError(e)),
+        };
+
+        let row = match sqlx::query!(
+            r"
+            SELECT q.id, q.user_id, q.accepted_answer_id
+            FROM answers a
+            JOIN questions q ON q.id = a.question_id
+            WHERE a.id = $1
+            FOR UPDATE OF q
+            ",
+            answer_id
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        {
+            Ok(Some(row)) => row,
+            Ok(None) => return Err(Error::NotFound),
+            Err(e) => return Err(Error::DbError(e)),
+        };
+
+        if row.user_id != Some(user_id) {
+            return Err(Error::NotOwner);
+        }
+
+        if row.accepted_answer_id == Some(answer_id) {
+            undo_accept(&mut tx, answer_id).await?;
+
+            if let Err(e) = sqlx::query!(
+                "UPDATE questions SET accepted_answer_id = NULL WHERE id = $1",
+                row.id
+            )
+            .execute(&mut *tx)
+            .await
+            {
+                return Err(Error::DbError(e));
+            }
+
+            event::publish(
+                &mut tx,
+                &Change::AnswerUnaccepted {
+                    question_id: row.id,
+                    answer_id,
+                },
+            )
+            .await?;
+        }
+
+        match tx.commit().await {
+            Ok(_) => {
+                record_rows(1);
+                Ok(())
+            }
+            Err(e) => Err(Error::DbError(e)),
+        }
+    }
+
+    /// The question's answers with their score, whether they are accepted and
+    /// a summary of their author.
+    #[instrument(name = "db.get_answers", skip_all, fields(db.rows))]
+    pub async fn get_answers(&self, question_id: Uuid) -> Result<Vec<AnswerView>, Error> {
+        match sqlx::query!(
+            r#"
+            SELECT a.id, a.content, a.content_html, a.question_id AS "question_id!", a.version,
+                COALESCE((SELECT SUM(value) FROM votes WHERE answer_id = a.id), 0) AS "score!",
+                q.accepted_answer_id IS NOT DISTINCT FROM a.id AS "accepted!",
+                u.id AS "author_id?", u.name AS "author_name?",
+                COALESCE(
+                    (SELECT SUM(delta) FROM reputation_events WHERE user_id = u.id), 0
+                ) AS "reputation!",
+                ARRAY(
+                    SELECT badge FROM user_badges WHERE user_id = u.id ORDER BY awarded_on
+                ) AS "badges!: Vec<BadgeKind>"
+            FROM answers a
+            JOIN questions q ON q.id = a.question_id
+            LEFT JOIN users u ON u.id = a.user_id
+            WHERE a.question_id = $1
+            ORDER BY a.created_on
+            "#,
+            question_id
+        )
+        .fetch_all(&self.conn)
+        .await
+        {
+            Ok(rows) => {
+                record_rows(rows.len());
+                Ok(rows
+                    .into_iter()
+                    .map(|row| AnswerView {
+                        answer: Answer {
+                            id: row.id,
+                            content: row.content,
+                            content_html: row.content_html,
+                            question_id: row.question_id,
+                            version: row.version,
+                        },
+                        score: row.score,
+                        accepted: row.accepted,
+                        author: row.author_id.zip(row.author_name).map(|(id, name)| {
+                            AuthorSummary {
+                                id,
+                                name,
+                                reputation: row.reputation,
+                                badges: row.badges,
+                            }
+                        }),
+                    })
+                    .collect())
+            }
+            Err(e) => Err(Error::DbError(e)),
+        }
+    }
+
+    #[instrument(name = "db.get_score", skip_all, fields(db.rows))]
+    pub async fn get_score(&self, target: VoteTarget) -> Result<i64, Error> {
+        let score = score(&self.conn, target).await?;
+        record_rows(1);
+        Ok(score)
+    }
+
+    /// The user's reputation: the sum of their ledger.
+    #[instrument(name = "db.get_reputation", skip_all, fields(db.rows))]
+    pub async fn get_reputation(&self, user_id: Uuid) -> Result<i64, Error> {
+        match sqlx::query_scalar!(
+            r#"
+            SELECT COALESCE(SUM(delta), 0) AS "reputation!"
+            FROM reputation_events WHERE user_id = $1
+            "#,
+            user_id
+        )
+        .fetch_one(&self.conn)
+        .await
+        {
+            Ok(reputation) => {
+                record_rows(1);
+                Ok(reputation)
+            }
+            Err(e) => Err(Error::DbError(e)),
+        }
+    }
+
+    #[instrument(name = "db.get_user_profile", skip_all, fields(db.rows))]
+    pub async fn get_user_profile(&self, user_id: Uuid) -> Result<UserProfile, Error> {
+        let name = match sqlx::query_scalar!("SELECT name FROM users WHERE id = $1", user_id)
+            .fetch_one(&self.conn)
+            .await
+        {
+            Ok(name) => name,
+            Err(sqlx::Error::RowNotFound) => return Err(Error::NotFound),
+            Err(e) => return Err(Error::DbError(e)),
+        };
+
+        let reputation = self.get_reputation(user_id).await?;
+
+        match sqlx::query_as!(
+            Badge,
+            r#"
+            SELECT badge AS "badge: BadgeKind", awarded_on
+            FROM user_badges WHERE user_id = $1
+            ORDER BY awarded_on
+            "#,
+            user_id
+        )
+        .fetch_all(&self.conn)
+        .await
+        {
+            Ok(badges) => {
+                record_rows(1 + badges.len());
+                Ok(UserProfile {
+                    id: user_id,
+                    name,
+                    reputation,
+                    badges,
+                })
+            }
+            Err(e) => Err(Error::DbError(e)),
+        }
+    }
+
+    /// The user's reputation ledger, newest first. Returns up to `limit`
+    /// entries after `before`.
+    #[instrument(name = "db.get_reputation_events", skip_all, fields(db.rows))]
+    pub async fn get_reputation_events(
+        &self,
+        user_id: Uuid,
+        before: Option<Cursor>,
+        limit: i64,
+    ) -> Result<Vec<ReputationEvent>, Error> {
+        match sqlx::query_as!(
+            ReputationEvent,
+            r#"
+            SELECT id, delta, reason AS "reason: ReputationReason", actor_id, question_id,
+                answer_id, created_on
+            FROM reputation_events
+            WHERE user_id = $1
+                AND ($2::timestamp IS NULL OR (created_on, id) < ($2, $3::uuid))
+            ORDER BY created_on DESC, id DESC
+            LIMIT $4
+            "#,
+            user_id,
+            before.map(|c| c.created_on),
+            before.map(|c| c.id),
+            limit
+        )
+        .fetch_all(&self.conn)
+        .await
+        {
+            Ok(events) => {
+                record_rows(events.len());
+                Ok(events)
+            }
+            Err(e) => Err(Error::DbError(e)),
+        }
+    }
+}
+
+/// Awards the badge unless the user already has it.
+pub(super) async fn award_badge(
+    tx: &mut Transaction<'static, Postgres>,
+    user_id: Uuid,
+    badge: BadgeKind,
+) -> Result<(), Error> {
+    match sqlx::query!(
+        r"
+        INSERT INTO user_badges (user_id, badge) VALUES ($1, $2)
+        ON CONFLICT DO NOTHING
+        ",
+        user_id,
+        badge as BadgeKind
+    )
+    .execute(&mut **tx)
+    .await
+    {
+        Ok(_) => Ok(()),
+        Err(e) => Err(Error::DbError(e)),
+    }
+}
+
+/// The author of the voted-on post, `None` when their account is gone, and
+/// the question it belongs to.
+async fn post(
+    tx: &mut Transaction<'static, Postgres>,
+    target: VoteTarget,
+) -> Result<(Option<Uuid>, Uuid), Error> {
+    let post = match target {
+        VoteTarget::Question(id) => sqlx::query!(
+            r#"SELECT user_id, id AS "question_id" FROM questions WHERE id = $1"#,
+            id
+        )
+        .fetch_one(&mut **tx)
+        .await
+        .map(|row| (row.user_id, row.question_id)),
+        VoteTarget::Answer(id) => sqlx::query!(
+            r#"SELECT user_id, question_id AS "question_id!" FROM answers WHERE id = $1"#,
+            id
+        )
+        .fetch_one(&mut **tx)
+        .await
+        .map(|row| (row.user_id, row.question_id)),
+    };
+
+    match post {
+        Ok(post) => Ok(post),
+        Err(sqlx::Error::RowNotFound) => Err(Error::NotFound),
+        Err(e) => Err(Error::DbError(e)),
+    }
+}
+
+async fn publish_vote(
+    tx: &mut Transaction<'static, Postgres>,
+    question_id: Uuid,
+    target: VoteTarget,
+) -> Result<(), Error> {
+    let (_, answer_id) = target.columns();
+    event::publish(
+        tx,
+        &Change::Voted {
+            question_id,
+            answer_id,
+        },
+    )
+    .await
+}
+
+async fn current_vote(
+    tx: &mut Transaction<'static, Postgres>,
+    user_id: Uuid,
+    target: VoteTarget,
+) -> Result<Option<i16>, Error> {
+    let (question_id, answer_id) = target.columns();
+
+    match sqlx::query_scalar!(
+        r"
+        SELECT value FROM votes
+        WHERE user_id = $1
+            AND question_id IS NOT DISTINCT FROM $2
+            AND answer_id IS NOT DISTINCT FROM $3
+        FOR UPDATE
+        ",
+        user_id,
+        question_id,
+        answer_id
+    )
+    .fetch_optional(&mut **tx)
+    .await
+    {
+        Ok(value) => Ok(value),
+        Err(e) => Err(Error::DbError(e)),
+    }
+}
+
+/// Deletes the user's vote on the target and cancels whatever it earned or
+/// cost the author.
+async fn remove_vote(
+    tx: &mut Transaction<'static, Postgres>,
+    user_id: Uuid,
+    target: VoteTarget,
+    author_id: Option<Uuid>,
+) -> Result<(), Error> {
+    let (question_id, answer_id) = target.columns();
+
+    if let Err(e) = sqlx::query!(
+        r"
+        DELETE FROM votes
+        WHERE user_id = $1
+            AND question_id IS NOT DISTINCT FROM $2
+            AND answer_id IS NOT DISTINCT FROM $3
+        ",
+        user_id,
+        question_id,
+        answer_id
+    )
+    .execute(&mut **tx)
+    .await
+    {
+        return Err(Error::DbError(e));
+    }
+
+    let Some(author_id) = author_id else {
+        return Ok(());
+    };
+
+    let earned = match sqlx::query_scalar!(
+        r#"
+        SELECT COALESCE(SUM(delta), 0) AS "earned!"
+        FROM reputation_events
+        WHERE user_id = $1 AND actor_id = $2
+            AND reason IN ('upvote', 'downvote', 'vote_undone')
+            AND question_id IS NOT DISTINCT FROM $3
+            AND answer_id IS NOT DISTINCT FROM $4
+        "#,
+        author_id,
+        user_id,
+        question_id,
+        answer_id
+    )
+    .fetch_one(&mut **tx)
+    .await
+    {
+        Ok(earned) => earned,
+        Err(e) => return Err(Error::DbError(e)),
+    };
+
+    if earned != 0 {
+        record(
+            tx,
+            author_id,
+            -(earned as i32),
+            ReputationReason::VoteUndone,
+            user_id,
+            question_id,
+            answer_id,
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Cancels whatever accepting the answer earned its author.
+async fn undo_accept(
+    tx: &mut Transaction<'static, Postgres>,
+    answer_id: Uuid,
+) -> Result<(), Error> {
+    let rows = match sqlx::query!(
+        r#"
+        SELECT user_id, actor_id, question_id, SUM(delta) AS "earned!"
+        FROM reputation_events
+        WHERE answer_id = $1 AND reason IN ('accepted', 'accept_undone')
+        GROUP BY user_id, actor_id, question_id
+        "#,
+        answer_id
+    )
+    .fetch_all(&mut **tx)
+    .await
+    {
+        Ok(rows) => rows,
+        Err(e) => return Err(Error::DbError(e)),
+    };
+
+    for row in rows.into_iter().filter(|row| row.earned != 0) {
+        if let Err(e) = sqlx::query!(
+            r"
+            INSERT INTO reputation_events
+                (id, user_id, delta, reason, actor_id, question_id, answer_id)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            ",
+            Uuid::new_v4(),
+            row.user_id,
+            -(row.earned as i32),
+            ReputationReason::AcceptUndone as ReputationReason,
+            row.actor_id,
+            row.question_id,
+            answer_id
+        )
+        .execute(&mut **tx)
+        .await
+        {
+            return Err(Error::DbError(e));
+        }
+    }
+
+    Ok(())
+}
+
+/// Points the user has gained from upvotes since midnight. Holds a lock on
+/// the user's daily cap until the transaction ends, so that concurrent upvotes
+/// for the same author see each other's points.
+async fn upvotes_today(
+    tx: &mut Transaction<'static, Postgres>,
+    user_id: Uuid,
+) -> Result<i64, Error> {
+    if let Err(e) = sqlx::query!(
+        "SELECT pg_advisory_xact_lock(hashtext('askly.daily_cap'), hashtext($1::text))",
+        user_id.to_string()
+    )
+    .execute(&mut **tx)
+    .await
+    {
+        return Err(Error::DbError(e));
+    }
+
+    match sqlx::query_scalar!(
+        r#"
+        SELECT COALESCE(SUM(delta), 0) AS "earned!"
+        FROM reputation_events
+        WHERE user_id = $1 AND reason = 'upvote' AND created_on >= CURRENT_DATE
+        "#,
+        user_id
+    )
+    .fetch_one(&mut **tx)
+    .await
+    {
+        Ok(earned) => Ok(earned),
+        Err(e) => Err(Error::DbError(e)),
+    }
+}
+
+/// How many of the user's answers are accepted on someone else's question.
+async fn accepted_count(
+    tx: &mut Transaction<'static, Postgres>,
+    user_id: Uuid,
+) -> Result<i64, Error> {
+    match sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*) AS "count!"
+        FROM questions q
+        JOIN answers a ON a.id = q.accepted_answer_id
+        WHERE a.user_id = $1 AND q.user_id IS DISTINCT FROM $1
+        "#,
+        user_id
+    )
+    .fetch_one(&mut **tx)
+    .await
+    {
+        Ok(count) => Ok(count),
+        Err(e) => Err(Error::DbError(e)),
+    }
+}
+
+async fn score(conn: impl PgExecutor<'_>, target: VoteTarget) -> Result<i64, Error> {
+    let (question_id, answer_id) = target.columns();
+
+    match sqlx::query_scalar!(
+        r#"
+        SELECT COALESCE(SUM(value), 0) AS "score!"
+        FROM votes
+        WHERE question_id IS NOT DISTINCT FROM $1 AND answer_id IS NOT DISTINCT FROM $2
+        "#,
+        question_id,
+        answer_id
+    )
+    .fetch_one(conn)
+    .await
+    {
+        Ok(score) => Ok(score),
+        Err(e) => Err(Error::DbError(e)),
+    }
+}
+
+async fn record(
+    tx: &mut Transaction<'static, Postgres>,
+    user_id: Uuid,
+    delta: i32,
+    reason: ReputationReason,
+    actor_id: Uuid,
+    question_id: Option<Uuid>,
+    answer_id: Option<Uuid>,
+) -> Result<(), Error> {
+    match sqlx::query!(
+        r"
+        INSERT INTO reputation_events
+            (id, user_id, delta, reason, actor_id, question_id, answer_id)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        ",
+        Uuid::new_v4(),
+        user_id,
+        delta,
+        reason as ReputationReason,
+        actor_id,
+        question_id,
+        answer_id
+    )
+    .execute(&mut **tx)
+    .await
+    {
+        Ok(_) => Ok(()),
+        Err(e) => Err(Error::DbError(e)),
+    }
+}
@@ -0,0 +1,259 @@
+use sqlx::{Postgres, Transaction};
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{
+    domain::{
+        event::Change,
+        question::{CloseReason, CloseVotes, NewCloseVote, Question, QuestionStatus},
+        webhook::WebhookEvent,
+    },
+    error::Error,
+};
+
+use super::{event, record_rows, webhook, DbStore};
+
+impl DbStore {
+    /// Records the caller's vote to close the open question, replacing any
+    /// earlier one. Once `needed` votes are in, the question is closed for the
+    /// reason most of them gave.
+    #[instrument(name = "db.add_close_vote", skip_all, fields(db.rows))]
+    pub async fn add_close_vote(
+        &self,
+        user_id: Uuid,
+        question_id: Uuid,
+        vote: &NewCloseVote,
+        needed: i64,
+    ) -> Result<CloseVotes, Error> {
+        let mut tx = match self.conn.begin().await {
+            Ok(tx) => tx,
+            Err(e) => return Err(Error::DbError(e)),
+        };
+
+        let status = lock_status(&mut tx, question_id).await?;
+        if status != QuestionStatus::Open {
+            return Err(Error::QuestionNotOpen(status));
+        }
+
+        match sqlx::query!(
+            r"
+            INSERT INTO close_votes (question_id, user_id, reason, duplicate_of)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (question_id, user_id)
+            DO UPDATE SET reason = $3, duplicate_of = $4, created_on = NOW()
+            ",
+            question_id,
+            user_id,
+            vote.reason as CloseReason,
+            vote.duplicate_of
+        )
+        .execute(&mut *tx)
+        .await
+        {
+            Ok(_) => {}
+            Err(sqlx::Error::Database(e)) if e.is_foreign_key_violation() => {
+                return Err(Error::InvalidInput(
+                    "`duplicate_of` is not a question".to_string(),
+                ))
+            }
+            Err(e) => return Err(Error::DbError(e)),
+        }
+
+        let votes = count_votes(&mut tx, question_id).await?;
+        let status = if votes >= needed {
+            let winner = match sqlx::query!(
+                r#"
+                SELECT reason AS "reason: CloseReason", duplicate_of
+                FROM close_votes WHERE question_id = $1
+                GROUP BY reason, duplicate_of
+                ORDER BY COUNT(*) DESC, MIN(created_on)
+                LIMIT 1
+                "#,
+                question_id
+            )
+            .fetch_one(&mut *tx)
+            .await
+            {
+                Ok(row) => row,
+                Err(e) => return Err(Error::DbError(e)),
+            };
+
+            apply_status(
+                &mut tx,
+                question_id,
+                QuestionStatus::Closed,
+                Some(winner.reason),
+                winner.duplicate_of,
+            )
+            .await?
+            .status
+        } else {
+            status
+        };
+
+        match tx.commit().await {
+            Ok(_) => {
+                record_rows(1);
+                Ok(CloseVotes {
+                    votes,
+                    needed,
+                    status,
+                })
+            }
+            Err(e) => Err(Error::DbError(e)),
+        }
+    }
+
+    /// Retracts the caller's pending vote to close the question.
+    #[instrument(name = "db.delete_close_vote", skip_all, fields(db.rows))]
+    pub async fn delete_close_vote(
+        &self,
+        user_id: Uuid,
+        question_id: Uuid,
+        needed: i64,
+    ) -> Result<CloseVotes, Error> {
+        let mut tx = match self.conn.begin().await {
+            Ok(tx) => tx,
+            Err(e) => return Err(Error::DbError(e)),
+        };
+
+        let status = lock_status(&mut tx, question_id).await?;
+
+        if let Err(e) = sqlx::query!(
+            "DELETE FROM close_votes WHERE question_id = $1 AND user_id = $2",
+            question_id,
+            user_id
+        )
+        .execute(&mut *tx)
+        .await
+        {
+            return Err(Error::DbError(e));
+        }
+
+        let votes = count_votes(&mut tx, question_id).await?;
+
+        match tx.commit().await {
+            Ok(_) => {
+                record_rows(1);
+                Ok(CloseVotes {
+                    votes,
+                    needed,
+                    status,
+                })
+            }
+            Err(e) => Err(Error::DbError(e)),
+        }
+    }
+
+    /// Sets the question's status regardless of close votes, which are
+    /// cleared.
+    #[instrument(name = "db.set_question_status", skip_all, fields(db.rows))]
+    pub async fn set_question_status(
+        &self,
+        question_id: Uuid,
+        status: QuestionStatus,
+        reason: Option<CloseReason>,
+        duplicate_of: Option<Uuid>,
+    ) -> Result<Question, Error> {
+        let mut tx = match self.conn.begin().await {
+            Ok(tx) => tx,
+            Err(e) => return Err(Error::DbError(e)),
+        };
+
+        lock_status(&mut tx, question_id).await?;
+        let question = apply_status(&mut tx, question_id, status, reason, duplicate_of).await?;
+
+        match tx.commit().await {
+            Ok(_) => {
+                record_rows(1);
+                Ok(question)
+            }
+            Err(e) => Err(Error::DbError(e)),
+        }
+    }
+}
+
+async fn lock_status(
+    tx: &mut Transaction<'static, Postgres>,
+    question_id: Uuid,
+) -> Result<QuestionStatus, Error> {
+    match sqlx::query_scalar!(
+        r#"SELECT status AS "status: QuestionStatus" FROM questions WHERE id = $1 FOR UPDATE"#,
+        question_id
+    )
+    .fetch_optional(&mut **tx)
+    .await
+    {
+        Ok(Some(status)) => Ok(status),
+        Ok(None) => Err(Error::NotFound),
+        Err(e) => Err(Error::DbError(e)),
+    }
+}
+
+async fn count_votes(
+    tx: &mut Transaction<'static, Postgres>,
+    question_id: Uuid,
+) -> Result<i64, Error> {
+    match sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!" FROM close_votes WHERE question_id = $1"#,
+        question_id
+    )
+    .fetch_one(&mut **tx)
+    .await
+    {
+        Ok(count) => Ok(count),
+        Err(e) => Err(Error::DbError(e)),
+    }
+}
+
+/// Updates the status, clears pending close votes and announces the change
+/// like an edit. The version is bumped so cached copies are revalidated.
+async fn apply_status(
+    tx: &mut Transaction<'static, Postgres>,
+    question_id: Uuid,
+    status: QuestionStatus,
+    reason: Option<CloseReason>,
+    duplicate_of: Option<Uuid>,
+) -> Result<Question, Error> {
+    let question = match sqlx::query_as!(
+        Question,
+        r#"
+        UPDATE questions
+        SET status = $1, close_reason = $2, duplicate_of = $3, version = version + 1
+        WHERE id = $4
+        RETURNING id, title, content, content_html, tags, version,
+            status AS "status: QuestionStatus", close_reason AS "close_reason: CloseReason",
+            duplicate_of, NULL::bool AS bookmarked
+        "#,
+        status as QuestionStatus,
+        reason as Option<CloseReason>,
+        duplicate_of,
+        question_id
+    )
+    .fetch_one(&mut **tx)
+    .await
+    {
+        Ok(question) => question,
+        Err(sqlx::Error::Database(e)) if e.is_foreign_key_violation() => {
+            return Err(Error::InvalidInput(
+                "`duplicate_of` is not a question".to_string(),
+            ))
+        }
+        Err(e) => return Err(Error::DbError(e)),
+    };
+
+    if let Err(e) = sqlx::query!(
+        "DELETE FROM close_votes WHERE question_id = $1",
+        question_id
+    )
+    .execute(&mut **tx)
+    .await
+    {
+        return Err(Error::DbError(e));
+    }
+
+    event::publish(tx, &Change::QuestionUpdated { question_id }).await?;
+    webhook::enqueue(tx, WebhookEvent::QuestionUpdated, &question).await?;
+
+    Ok(question)
+}
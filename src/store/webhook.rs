@@ -0,0 +1,249 @@
+use chrono::Utc;
+use serde::Serialize;
+use sqlx::{Postgres, Transaction};
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::{
+    domain::webhook::{DeliveryStatus, PendingDelivery, Webhook, WebhookDelivery, WebhookEvent},
+    error::Error,
+};
+
+use super::{record_rows, DbStore};
+
+/// Body of every delivery.
+#[derive(Serialize)]
+struct Payload<'a, T> {
+    event: &'a str,
+    created_on: String,
+    data: &'a T,
+}
+
+impl DbStore {
+    #[instrument(name = "db.add_webhook", skip_all, fields(db.rows))]
+    pub async fn add_webhook(&self, webhook: &Webhook, created_by: Uuid) -> Result<(), Error> {
+        match sqlx::query!(
+            r"
+            INSERT INTO webhooks (id, url, secret, events, created_by, created_on)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ",
+            webhook.id,
+            webhook.url,
+            webhook.secret,
+            &webhook.events,
+            created_by,
+            webhook.created_on
+        )
+        .execute(&self.conn)
+        .await
+        {
+            Ok(result) => {
+                record_rows(result.rows_affected() as usize);
+                Ok(())
+            }
+            Err(e) => Err(Error::DbError(e)),
+        }
+    }
+
+    /// Lists webhooks without their secrets.
+    #[instrument(name = "db.get_webhooks", skip_all, fields(db.rows))]
+    pub async fn get_webhooks(&self) -> Result<Vec<Webhook>, Error> {
+        match sqlx::query_as!(
+            Webhook,
+            r#"
+            SELECT id, url, events, NULL::text AS secret, created_on
+            FROM webhooks ORDER BY created_on
+            "#
+        )
+        .fetch_all(&self.conn)
+        .await
+        {
+            Ok(webhooks) => {
+                record_rows(webhooks.len());
+                Ok(webhooks)
+            }
+            Err(e) => Err(Error::DbError(e)),
+        }
+    }
+
+    /// Deletes the webhook together with its delivery log.
+    #[instrument(name = "db.delete_webhook", skip_all, fields(db.rows))]
+    pub async fn delete_webhook(&self, id: Uuid) -> Result<(), Error> {
+        match sqlx::query!("DELETE FROM webhooks WHERE id = $1", id)
+            .execute(&self.conn)
+            .await
+        {
+            Ok(result) if result.rows_affected() == 0 => Err(Error::NotFound),
+            Ok(result) => {
+                record_rows(result.rows_affected() as usize);
+                Ok(())
+            }
+            Err(e) => Err(Error::DbError(e)),
+        }
+    }
+
+    /// The most recent deliveries of a webhook, newest first.
+    #[instrument(name = "db.get_webhook_deliveries", skip_all, fields(db.rows))]
+    pub async fn get_webhook_deliveries(
+        &self,
+        webhook_id: Uuid,
+        limit: i64,
+    ) -> Result<Vec<WebhookDelivery>, Error> {
+        match sqlx::query_as!(
+            WebhookDelivery,
+            r#"
+            SELECT id, event, status AS "status: DeliveryStatus", attempts, response_status,
+                error, next_attempt_on, created_on, delivered_on
+            FROM webhook_deliveries
+            WHERE webhook_id = $1
+            ORDER BY created_on DESC
+            LIMIT $2
+            "#,
+            webhook_id,
+            limit
+        )
+        .fetch_all(&self.conn)
+        .await
+        {
+            Ok(deliveries) => {
+                record_rows(deliveries.len());
+                Ok(deliveries)
+            }
+            Err(e) => Err(Error::DbError(e)),
+        }
+    }
+
+    /// Claims up to `limit` due deliveries by pushing their next attempt
+    /// `lease_secs` into the future, so that other workers skip them while
+    /// they are being sent. A worker that dies mid-send leaves them to be
+    /// retried once the lease runs out.
+    #[instrument(name = "db.claim_webhook_deliveries", skip_all, fields(db.rows))]
+    pub async fn claim_webhook_deliveries(
+        &self,
+        limit: i64,
+        lease_secs: f64,
+    ) -> Result<Vec<PendingDelivery>, Error> {
+        match sqlx::query_as!(
+            PendingDelivery,
+            r"
+            UPDATE webhook_deliveries d
+            SET next_attempt_on = NOW() + make_interval(secs => $2)
+            FROM webhooks w
+            WHERE w.id = d.webhook_id AND d.id IN (
+                SELECT id FROM webhook_deliveries
+                WHERE status = 'pending' AND next_attempt_on <= NOW()
+                ORDER BY next_attempt_on
+                LIMIT $1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING d.id, w.url, w.secret, d.event, d.payload, d.attempts
+            ",
+            limit,
+            lease_secs
+        )
+        .fetch_all(&self.conn)
+        .await
+        {
+            Ok(deliveries) => {
+                record_rows(deliveries.len());
+                Ok(deliveries)
+            }
+            Err(e) => Err(Error::DbError(e)),
+        }
+    }
+
+    #[instrument(name = "db.mark_webhook_delivered", skip_all, fields(db.rows))]
+    pub async fn mark_webhook_delivered(
+        &self,
+        id: Uuid,
+        response_status: i32,
+    ) -> Result<(), Error> {
+        match sqlx::query!(
+            r"
+            UPDATE webhook_deliveries
+            SET status = 'delivered', attempts = attempts + 1, response_status = $2,
+                error = NULL, delivered_on = NOW()
+            WHERE id = $1
+            ",
+            id,
+            response_status
+        )
+        .execute(&self.conn)
+        .await
+        {
+            Ok(result) => {
+                record_rows(result.rows_affected() as usize);
+                Ok(())
+            }
+            Err(e) => Err(Error::DbError(e)),
+        }
+    }
+
+    /// Records a failed attempt. The delivery is retried after `retry_secs`,
+    /// or marked failed for good when that is `None`.
+    #[instrument(name = "db.mark_webhook_failed", skip_all, fields(db.rows))]
+    pub async fn mark_webhook_failed(
+        &self,
+        id: Uuid,
+        response_status: Option<i32>,
+        error: &str,
+        retry_secs: Option<f64>,
+    ) -> Result<(), Error> {
+        match sqlx::query!(
+            r"
+            UPDATE webhook_deliveries
+            SET attempts = attempts + 1, response_status = $2, error = $3,
+                status = CASE WHEN $4::float8 IS NULL
+                    THEN 'failed'::delivery_status ELSE 'pending' END,
+                next_attempt_on = NOW() + make_interval(secs => COALESCE($4, 0))
+            WHERE id = $1
+            ",
+            id,
+            response_status,
+            error,
+            retry_secs
+        )
+        .execute(&self.conn)
+        .await
+        {
+            Ok(result) => {
+                record_rows(result.rows_affected() as usize);
+                Ok(())
+            }
+            Err(e) => Err(Error::DbError(e)),
+        }
+    }
+}
+
+/// Queues a delivery of `data` to every webhook subscribed to `event`. The
+/// rows commit or roll back with the change they describe.
+pub(super) async fn enqueue(
+    tx: &mut Transaction<'static, Postgres>,
+    event: WebhookEvent,
+    data: &impl Serialize,
+) -> Result<(), Error> {
+    let payload = Payload {
+        event: event.as_str(),
+        created_on: Utc::now().to_rfc3339(),
+        data,
+    };
+    let payload = match serde_json::to_string(&payload) {
+        Ok(payload) => payload,
+        Err(_) => return Err(Error::ServerError),
+    };
+
+    match sqlx::query!(
+        r"
+        INSERT INTO webhook_deliveries (id, webhook_id, event, payload)
+        SELECT gen_random_uuid(), id, $1, $2 FROM webhooks WHERE $1 = ANY(events)
+        ",
+        event.as_str(),
+        payload
+    )
+    .execute(&mut **tx)
+    .await
+    {
+        Ok(_) => Ok(()),
+        Err(e) => Err(Error::DbError(e)),
+    }
+}
@@ -0,0 +1,278 @@
+//! Delivery of queued webhook events.
+//!
+//! Store mutations queue a row per subscribed webhook in
+//! `webhook_deliveries`; the worker here claims due rows, POSTs them signed
+//! and records each attempt. Several instances can run a worker at once.
+
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use bytes::Bytes;
+use chrono::Utc;
+use futures_util::future::join_all;
+use hmac::{Hmac, Mac};
+use reqwest::Client;
+use sha2::Sha256;
+use tokio::{sync::mpsc, task::JoinHandle};
+use tracing::{debug, warn};
+use warp::{
+    http::{HeaderMap, StatusCode},
+    Filter, Rejection,
+};
+
+use crate::{domain::webhook::PendingDelivery, shutdown::Shutdown, store::DbStore};
+
+/// Attempts before a delivery is marked failed.
+pub const MAX_ATTEMPTS: i32 = 8;
+/// Delay before the first retry; doubled after every failed attempt.
+const RETRY_BASE_SECS: f64 = 10.0;
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+const BATCH: i64 = 20;
+const TIMEOUT: Duration = Duration::from_secs(10);
+/// How long a claimed delivery is hidden from other workers; comfortably
+/// longer than a request can take.
+const LEASE_SECS: f64 = 60.0;
+
+/// Starts the delivery worker. Once `shutdown` fires it finishes the batch
+/// in flight and stops.
+pub fn spawn(store: DbStore, mut shutdown: Shutdown) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let client = match Client::builder().timeout(TIMEOUT).build() {
+            Ok(client) => client,
+            Err(e) => {
+                warn!("webhooks disabled, failed to build HTTP client: {}", e);
+                return;
+            }
+        };
+
+        loop {
+            match store.claim_webhook_deliveries(BATCH, LEASE_SECS).await {
+                Ok(deliveries) if !deliveries.is_empty() => {
+                    let full = deliveries.len() as i64 == BATCH;
+                    join_all(deliveries.into_iter().map(|d| deliver(&store, &client, d))).await;
+                    // More may be due already.
+                    if full && !shutdown.is_fired() {
+                        continue;
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => warn!("failed to claim webhook deliveries: {}", e),
+            }
+
+            tokio::select! {
+                _ = tokio::time::sleep(POLL_INTERVAL) => {}
+                _ = shutdown.wait() => {
+                    debug!("webhook worker stopped");
+                    return;
+                }
+            }
+        }
+    })
+}
+
+async fn deliver(store: &DbStore, client: &Client, delivery: PendingDelivery) {
+    let timestamp = Utc::now().timestamp().to_string();
+    let signature = sign(&delivery.secret, &timestamp, &delivery.payload);
+
+    let result = client
+        .post(&delivery.url)
+        .header("content-type", "application/json")
+        .header("x-askly-event", &delivery.event)
+        .header("x-askly-delivery", delivery.id.to_string())
+        .header("x-askly-timestamp", &timestamp)
+        .header("x-askly-signature", format!("sha256={}", signature))
+        .body(delivery.payload.clone())
+        .send()
+        .await;
+
+    let (status, error) = match result {
+        Ok(response) if response.status().is_success() => {
+            debug!("delivered webhook {}", delivery.id);
+            let status = response.status().as_u16() as i32;
+            if let Err(e) = store.mark_webhook_delivered(delivery.id, status).await {
+                warn!("failed to record webhook delivery {}: {}", delivery.id, e);
+            }
+            return;
+        }
+        Ok(response) => (
+            Some(response.status().as_u16() as i32),
+            format!("receiver answered {}", response.status()),
+        ),
+        Err(e) => (None, e.to_string()),
+    };
+
+    let attempt = delivery.attempts + 1;
+    let retry_secs = retry_delay(attempt);
+    debug!(
+        "webhook {} attempt {} failed: {}",
+        delivery.id, attempt, error
+    );
+
+    if let Err(e) = store
+        .mark_webhook_failed(delivery.id, status, &error, retry_secs)
+        .await
+    {
+        warn!("failed to record webhook delivery {}: {}", delivery.id, e);
+    }
+}
+
+/// Seconds to wait after failed attempt number `attempt` (1-based), or
+/// `None` once the delivery has run out of attempts.
+fn retry_delay(attempt: i32) -> Option<f64> {
+    (attempt < MAX_ATTEMPTS).then(|| RETRY_BASE_SECS * 2f64.powi(attempt - 1))
+}
+
+/// Hex HMAC-SHA256 of `<timestamp>.<body>`, sent as
+/// `X-Askly-Signature: sha256=<hex>`. Covering the timestamp lets receivers
+/// reject replays of old deliveries.
+pub fn sign(secret: &str, timestamp: &str, body: &str) -> String {
+    hex::encode(mac(secret, timestamp, body).finalize().into_bytes())
+}
+
+/// Checks an `X-Askly-Signature` header value in constant time.
+pub fn verify(secret: &str, timestamp: &str, body: &str, signature: &str) -> bool {
+    let Some(hex_signature) = signature.strip_prefix("sha256=") else {
+        return false;
+    };
+    match hex::decode(hex_signature) {
+        Ok(bytes) => mac(secret, timestamp, body).verify_slice(&bytes).is_ok(),
+        Err(_) => false,
+    }
+}
+
+fn mac(secret: &str, timestamp: &str, body: &str) -> Hmac<Sha256> {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(timestamp.as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+    mac
+}
+
+/// A delivery as the [`receiver`] saw it.
+#[derive(Debug)]
+pub struct Received {
+    /// Counts deliveries from 1.
+    pub number: usize,
+    pub event: String,
+    pub delivery: String,
+    pub verified: bool,
+    pub status: StatusCode,
+    pub body: String,
+}
+
+/// A stand-in webhook endpoint for local testing. It checks every signature
+/// against `secret`, answers 500 to the first `fail` correctly signed
+/// deliveries to exercise retries, and reports each delivery on `sink`.
+pub fn receiver(
+    secret: String,
+    fail: usize,
+    sink: mpsc::UnboundedSender<Received>,
+) -> impl Filter<Extract = (StatusCode,), Error = Rejection> + Clone {
+    let received = Arc::new(AtomicUsize::new(0));
+
+    warp::post()
+        .and(warp::header::headers_cloned())
+        .and(warp::body::bytes())
+        .map(move |headers: HeaderMap, body: Bytes| {
+            let number = received.fetch_add(1, Ordering::SeqCst) + 1;
+            let header = |name: &str| {
+                headers
+                    .get(name)
+                    .and_then(|v| v.to_str().ok())
+                    .unwrap_or_default()
+                    .to_string()
+            };
+
+            let body = String::from_utf8_lossy(&body).into_owned();
+            let verified = verify(
+                &secret,
+                &header("x-askly-timestamp"),
+                &body,
+                &header("x-askly-signature"),
+            );
+
+            let status = match (verified, number <= fail) {
+                (false, _) => StatusCode::UNAUTHORIZED,
+                (true, true) => StatusCode::INTERNAL_SERVER_ERROR,
+                (true, false) => StatusCode::NO_CONTENT,
+            };
+
+            sink.send(Received {
+                number,
+                event: header("x-askly-event"),
+                delivery: header("x-askly-delivery"),
+                verified,
+                status,
+                body,
+            })
+            .ok();
+
+            status
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &str = "secret";
+    const TIMESTAMP: &str = "1700000000";
+    const BODY: &str = r#"{"id":"1"}"#;
+
+    fn signature() -> String {
+        format!("sha256={}", sign(SECRET, TIMESTAMP, BODY))
+    }
+
+    #[test]
+    fn signatures_verify() {
+        assert!(verify(SECRET, TIMESTAMP, BODY, &signature()));
+    }
+
+    #[test]
+    fn tampered_deliveries_fail_verification() {
+        let signature = signature();
+
+        assert!(!verify(SECRET, TIMESTAMP, r#"{"id":"2"}"#, &signature));
+        assert!(!verify(SECRET, "1700000001", BODY, &signature));
+        assert!(!verify("other", TIMESTAMP, BODY, &signature));
+
+        let mut flipped = signature.clone().into_bytes();
+        let last = flipped.len() - 1;
+        flipped[last] = if flipped[last] == b'0' { b'1' } else { b'0' };
+        let flipped = String::from_utf8(flipped).unwrap();
+        assert!(!verify(SECRET, TIMESTAMP, BODY, &flipped));
+    }
+
+    #[test]
+    fn malformed_signatures_fail_verification() {
+        let hex = sign(SECRET, TIMESTAMP, BODY);
+
+        assert!(!verify(SECRET, TIMESTAMP, BODY, &hex));
+        assert!(!verify(SECRET, TIMESTAMP, BODY, "sha256=not-hex"));
+        assert!(!verify(SECRET, TIMESTAMP, BODY, "sha256="));
+        assert!(!verify(SECRET, TIMESTAMP, BODY, &format!("sha1={}", hex)));
+    }
+
+    #[test]
+    fn retries_back_off_exponentially() {
+        let delays: Vec<_> = (1..MAX_ATTEMPTS).map(retry_delay).collect();
+        let expected: Vec<_> = [10.0, 20.0, 40.0, 80.0, 160.0, 320.0, 640.0]
+            .into_iter()
+            .map(Some)
+            .collect();
+
+        assert_eq!(delays, expected);
+    }
+
+    #[test]
+    fn deliveries_give_up_after_the_last_attempt() {
+        assert_eq!(retry_delay(MAX_ATTEMPTS), None);
+        assert_eq!(retry_delay(MAX_ATTEMPTS + 1), None);
+    }
+}
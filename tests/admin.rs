@@ -0,0 +1,56 @@
+mod common;
+
+use askly::domain::user::Role;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use common::App;
+
+async fn attach(app: &App, user: Uuid, question: Option<Uuid>, answer: Option<Uuid>) -> Uuid {
+    let id = Uuid::new_v4();
+    sqlx::query(
+        "INSERT INTO attachments (id, question_id, answer_id, user_id, filename, content_type, size)
+         VALUES ($1, $2, $3, $4, 'a.txt', 'text/plain', 1)",
+    )
+    .bind(id)
+    .bind(question)
+    .bind(answer)
+    .bind(user)
+    .execute(&app.store.conn)
+    .await
+    .unwrap();
+    id
+}
+
+#[sqlx::test(migrator = "askly::store::MIGRATOR")]
+async fn deleting_user_content_returns_its_attachments(pool: PgPool) {
+    let app = App::new(pool);
+    let spammer = app.user("spammer", Role::User).await;
+    let other = app.user("other", Role::User).await;
+
+    let spam = app.question(&spammer).await;
+    let reply = app.answer(&other, spam).await;
+    let kept = app.question(&other).await;
+    let spam_answer = app.answer(&spammer, kept).await;
+
+    let mut expected = vec![
+        attach(&app, spammer.id, Some(spam), None).await,
+        attach(&app, other.id, None, Some(reply)).await,
+        attach(&app, spammer.id, None, Some(spam_answer)).await,
+    ];
+    let untouched = attach(&app, other.id, Some(kept), None).await;
+
+    let (deleted, mut attachments) = app.store.delete_user_content(spammer.id).await.unwrap();
+
+    // The question, the answer to it and the answer elsewhere.
+    assert_eq!(deleted, 3);
+    attachments.sort();
+    expected.sort();
+    assert_eq!(attachments, expected);
+
+    let remaining: Vec<Uuid> = sqlx::query_scalar("SELECT id FROM attachments")
+        .fetch_all(&app.store.conn)
+        .await
+        .unwrap();
+    assert_eq!(remaining, vec![untouched]);
+}
@@ -0,0 +1,51 @@
+mod common;
+
+use askly::domain::user::Role;
+use sqlx::PgPool;
+use warp::http::StatusCode;
+
+use common::App;
+
+#[sqlx::test(migrator = "askly::store::MIGRATOR")]
+async fn disabling_a_user_revokes_their_token(pool: PgPool) {
+    let app = App::new(pool);
+    let user = app.user("user", Role::User).await;
+    let id = app.question(&user).await;
+
+    let response = app
+        .request("GET", "/v1/me/notifications", Some(&user), None)
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    app.store.set_user_disabled(user.id, true).await.unwrap();
+
+    let response = app
+        .request("GET", "/v1/me/notifications", Some(&user), None)
+        .await;
+    assert_eq!(response.status, StatusCode::FORBIDDEN);
+    assert_eq!(response.body["message"], "Account is disabled");
+
+    // Optional authentication rejects the token too, rather than serving
+    // the request as anonymous.
+    let response = app
+        .request("GET", &format!("/v1/questions/{}", id), Some(&user), None)
+        .await;
+    assert_eq!(response.status, StatusCode::FORBIDDEN);
+}
+
+#[sqlx::test(migrator = "askly::store::MIGRATOR")]
+async fn tokens_of_deleted_users_are_invalid(pool: PgPool) {
+    let app = App::new(pool);
+    let user = app.user("user", Role::User).await;
+
+    sqlx::query("DELETE FROM users WHERE id = $1")
+        .bind(user.id)
+        .execute(&app.store.conn)
+        .await
+        .unwrap();
+
+    let response = app
+        .request("GET", "/v1/me/notifications", Some(&user), None)
+        .await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+}
@@ -0,0 +1,155 @@
+//! Drives the whole API through `warp::test` against a database that
+//! `#[sqlx::test]` creates and migrates for each test.
+
+#![allow(dead_code)]
+
+use askly::{build_routes, config::Config, domain::user::Role, events::EventBus, store::DbStore};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use uuid::Uuid;
+use warp::http::{HeaderMap, StatusCode};
+
+pub struct App {
+    pub store: DbStore,
+    pub config: Config,
+}
+
+pub struct Response {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Value,
+}
+
+/// A signed-in account.
+pub struct User {
+    pub id: Uuid,
+    pub token: String,
+}
+
+impl App {
+    pub fn new(pool: PgPool) -> Self {
+        Self::with_config(pool, |_| None)
+    }
+
+    /// Overrides config keys, e.g. `CLOSE_VOTES`, on top of the test defaults.
+    pub fn with_config(pool: PgPool, overrides: impl Fn(&str) -> Option<String>) -> Self {
+        let config = Config::from_source(|key| {
+            overrides(key).or_else(|| match key {
+                "DATABASE_URL" => Some("postgres://unused".to_string()),
+                "JWT_SECRET" => Some("test-secret".to_string()),
+                _ => None,
+            })
+        })
+        .expect("valid test config");
+
+        App {
+            store: DbStore { conn: pool },
+            config,
+        }
+    }
+
+    pub async fn request(
+        &self,
+        method: &str,
+        path: &str,
+        user: Option<&User>,
+        body: Option<Value>,
+    ) -> Response {
+        self.send(method, path, user, body, &[]).await
+    }
+
+    /// [`App::request`] with extra headers.
+    pub async fn send(
+        &self,
+        method: &str,
+        path: &str,
+        user: Option<&User>,
+        body: Option<Value>,
+        headers: &[(&str, &str)],
+    ) -> Response {
+        let mut request = warp::test::request().method(method).path(path);
+        if let Some(user) = user {
+            request = request.header("authorization", &user.token);
+        }
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+        if let Some(body) = body {
+            request = request.json(&body);
+        }
+
+        let routes = build_routes(self.store.clone(), self.config.clone(), EventBus::new()).await;
+        let response = request.reply(&routes).await;
+
+        let body = match response.body().is_empty() {
+            true => Value::Null,
+            false => serde_json::from_slice(response.body()).expect("JSON body"),
+        };
+        Response {
+            status: response.status(),
+            headers: response.headers().clone(),
+            body,
+        }
+    }
+
+    /// Signs up `name@example.com` with `role` and signs in.
+    pub async fn user(&self, name: &str, role: Role) -> User {
+        let email = format!("{}@example.com", name);
+        let credential = json!({ "email": email, "password": "password" });
+
+        let mut signup = credential.clone();
+        signup["name"] = json!(name);
+        let response = self.request("POST", "/v1/signup", None, Some(signup)).await;
+        assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+        let id = sqlx::query_scalar("SELECT id FROM users WHERE email = $1")
+            .bind(&email)
+            .fetch_one(&self.store.conn)
+            .await
+            .expect("signed up user");
+        self.store.set_user_role(id, role).await.expect("role set");
+
+        let response = self
+            .request("POST", "/v1/signin", None, Some(credential))
+            .await;
+        assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+        User {
+            id,
+            token: response.body.as_str().expect("token").to_string(),
+        }
+    }
+
+    /// Posts a question as `user` and returns its id.
+    pub async fn question(&self, user: &User) -> Uuid {
+        let question = json!({ "title": "How?", "content": "Like this?" });
+        let response = self
+            .request("POST", "/v1/questions", Some(user), Some(question))
+            .await;
+        assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+        response.body["id"]
+            .as_str()
+            .and_then(|id| id.parse().ok())
+            .expect("question id")
+    }
+
+    /// Answers `question` as `user` and returns the answer's id.
+    pub async fn answer(&self, user: &User, question: Uuid) -> Uuid {
+        let answer = json!({ "content": "Like that." });
+        let response = self
+            .request(
+                "POST",
+                &format!("/v1/questions/{}/answers", question),
+                Some(user),
+                Some(answer),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+        response.body["id"]
+            .as_str()
+            .and_then(|id| id.parse().ok())
+            .expect("answer id")
+    }
+}
@@ -0,0 +1,55 @@
+mod common;
+
+use askly::domain::user::Role;
+use sqlx::PgPool;
+use warp::http::StatusCode;
+
+use common::App;
+
+#[sqlx::test(migrator = "askly::store::MIGRATOR")]
+async fn bookmarking_changes_the_etag(pool: PgPool) {
+    let app = App::new(pool);
+    let user = app.user("user", Role::User).await;
+    let id = app.question(&user).await;
+    let path = format!("/v1/questions/{}", id);
+
+    let response = app.request("GET", &path, Some(&user), None).await;
+    assert_eq!(response.headers["vary"], "Authorization");
+    let before = response.headers["etag"].to_str().unwrap().to_string();
+    assert_eq!(before, "\"1+not-bookmarked\"");
+
+    let bookmark = format!("/v1/questions/{}/bookmark", id);
+    let response = app.request("PUT", &bookmark, Some(&user), None).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    // The cached copy says `bookmarked: false`, so it must not revalidate.
+    let response = app
+        .send(
+            "GET",
+            &path,
+            Some(&user),
+            None,
+            &[("if-none-match", &before)],
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.body["bookmarked"], true);
+    let after = response.headers["etag"].to_str().unwrap().to_string();
+    assert_eq!(after, "\"1+bookmarked\"");
+
+    let response = app
+        .send(
+            "GET",
+            &path,
+            Some(&user),
+            None,
+            &[("if-none-match", &after)],
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::NOT_MODIFIED);
+    assert_eq!(response.headers["vary"], "Authorization");
+
+    // Anonymous callers get the plain version tag.
+    let response = app.request("GET", &path, None, None).await;
+    assert_eq!(response.headers["etag"], "\"1\"");
+}
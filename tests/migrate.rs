@@ -0,0 +1,47 @@
+use askly::{
+    migrate::{self, MigrationState},
+    store::{DbStore, MIGRATOR},
+};
+use sqlx::PgPool;
+
+fn versions() -> Vec<i64> {
+    let mut versions: Vec<i64> = MIGRATOR
+        .iter()
+        .filter(|m| m.migration_type.is_up_migration())
+        .map(|m| m.version)
+        .collect();
+    versions.sort_unstable();
+    versions
+}
+
+async fn pending(store: &DbStore) -> Vec<i64> {
+    migrate::status(store)
+        .await
+        .unwrap()
+        .into_iter()
+        .filter(|m| m.state == MigrationState::Pending)
+        .map(|m| m.version)
+        .collect()
+}
+
+#[sqlx::test(migrator = "askly::store::MIGRATOR")]
+async fn down_to_the_current_version_reverts_nothing(pool: PgPool) {
+    let store = DbStore { conn: pool };
+    let latest = *versions().last().unwrap();
+
+    assert_eq!(migrate::down(&store, Some(latest)).await.unwrap(), None);
+    assert_eq!(migrate::down(&store, Some(latest + 1)).await.unwrap(), None);
+    assert!(pending(&store).await.is_empty());
+}
+
+#[sqlx::test(migrator = "askly::store::MIGRATOR")]
+async fn down_reverts_the_latest_migration(pool: PgPool) {
+    let store = DbStore { conn: pool };
+    let versions = versions();
+    let [.., previous, latest] = versions.as_slice() else {
+        panic!("fewer than two migrations");
+    };
+
+    assert_eq!(migrate::down(&store, None).await.unwrap(), Some(*previous));
+    assert_eq!(pending(&store).await, vec![*latest]);
+}
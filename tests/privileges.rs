@@ -0,0 +1,124 @@
+mod common;
+
+use std::time::Duration;
+
+use askly::domain::user::Role;
+use serde_json::json;
+use sqlx::PgPool;
+use warp::http::StatusCode;
+
+use common::App;
+
+#[sqlx::test(migrator = "askly::store::MIGRATOR")]
+async fn edits_need_the_privilege_before_the_question_is_locked(pool: PgPool) {
+    let app = App::new(pool.clone());
+    let author = app.user("author", Role::User).await;
+    let other = app.user("other", Role::User).await;
+    let id = app.question(&author).await;
+
+    // Another writer holds the row; a caller without the privilege is turned
+    // away without waiting for it.
+    let mut writer = pool.begin().await.unwrap();
+    sqlx::query("SELECT 1 FROM questions WHERE id = $1 FOR UPDATE")
+        .bind(id)
+        .execute(&mut *writer)
+        .await
+        .unwrap();
+
+    let edit = json!({ "title": "Why?", "content": "Because." });
+    let response = tokio::time::timeout(
+        Duration::from_secs(5),
+        app.send(
+            "PUT",
+            &format!("/v1/questions/{}", id),
+            Some(&other),
+            Some(edit),
+            &[("if-match", "\"1\"")],
+        ),
+    )
+    .await
+    .expect("the edit waited for the row lock");
+    assert_eq!(response.status, StatusCode::FORBIDDEN);
+    assert_eq!(
+        response.body["message"],
+        "Editing other users' posts requires 2000 reputation; you have 0"
+    );
+
+    writer.rollback().await.unwrap();
+}
+
+#[sqlx::test(migrator = "askly::store::MIGRATOR")]
+async fn authors_and_admins_edit_without_reputation(pool: PgPool) {
+    let app = App::new(pool);
+    let author = app.user("author", Role::User).await;
+    let admin = app.user("admin", Role::Admin).await;
+    let id = app.question(&author).await;
+    let path = format!("/v1/questions/{}", id);
+
+    let edit = json!({ "title": "Why?", "content": "Because." });
+    let response = app
+        .send(
+            "PUT",
+            &path,
+            Some(&author),
+            Some(edit.clone()),
+            &[("if-match", "\"1\"")],
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    let response = app
+        .send(
+            "PUT",
+            &path,
+            Some(&admin),
+            Some(edit),
+            &[("if-match", "\"2\"")],
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+}
+
+#[sqlx::test(migrator = "askly::store::MIGRATOR")]
+async fn close_votes_need_the_privilege(pool: PgPool) {
+    let app = App::new(pool);
+    let author = app.user("author", Role::User).await;
+    let admin = app.user("admin", Role::Admin).await;
+    let id = app.question(&author).await;
+    let path = format!("/v1/questions/{}/close-vote", id);
+
+    let vote = json!({ "reason": "unclear" });
+    let response = app
+        .request("PUT", &path, Some(&author), Some(vote.clone()))
+        .await;
+    assert_eq!(response.status, StatusCode::FORBIDDEN);
+    assert_eq!(
+        response.body["message"],
+        "Voting to close questions requires 3000 reputation; you have 0"
+    );
+
+    let response = app.request("PUT", &path, Some(&admin), Some(vote)).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+}
+
+#[sqlx::test(migrator = "askly::store::MIGRATOR")]
+async fn close_votes_can_be_retracted_without_the_privilege(pool: PgPool) {
+    let open = App::with_config(pool.clone(), |key| {
+        (key == "PRIVILEGE_VOTE_TO_CLOSE").then(|| "0".to_string())
+    });
+    let author = open.user("author", Role::User).await;
+    let voter = open.user("voter", Role::User).await;
+    let id = open.question(&author).await;
+    let path = format!("/v1/questions/{}/close-vote", id);
+
+    let vote = json!({ "reason": "unclear" });
+    let response = open.request("PUT", &path, Some(&voter), Some(vote)).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert_eq!(response.body["votes"], 1);
+
+    // The threshold is raised past the voter's reputation.
+    let app = App::new(pool);
+    let response = app.request("DELETE", &path, Some(&voter), None).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert_eq!(response.body["votes"], 0);
+}
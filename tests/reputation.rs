@@ -0,0 +1,161 @@
+mod common;
+
+use askly::{
+    domain::{event::Change, reputation::VoteTarget, user::Role},
+    store::EVENTS_CHANNEL,
+};
+use serde_json::json;
+use sqlx::{postgres::PgListener, PgPool};
+use warp::http::StatusCode;
+
+use common::App;
+
+#[sqlx::test(migrator = "askly::store::MIGRATOR")]
+async fn concurrent_first_votes_count_once(pool: PgPool) {
+    let app = App::new(pool);
+    let author = app.user("author", Role::User).await;
+    let voter = app.user("voter", Role::User).await;
+    let question = app.question(&author).await;
+    let target = VoteTarget::Question(question);
+    let points = &app.config.reputation;
+
+    let votes =
+        futures_util::future::join_all((0..4).map(|_| app.store.vote(voter.id, target, 1, points)))
+            .await;
+
+    for vote in votes {
+        assert_eq!(vote.unwrap().score, 1);
+    }
+    assert_eq!(
+        app.store.get_reputation(author.id).await.unwrap(),
+        i64::from(points.question_upvote)
+    );
+}
+
+#[sqlx::test(migrator = "askly::store::MIGRATOR")]
+async fn concurrent_upvotes_respect_the_daily_cap(pool: PgPool) {
+    let app = App::with_config(pool.clone(), |key| match key {
+        "REPUTATION_QUESTION_UPVOTE" => Some("5".to_string()),
+        "REPUTATION_DAILY_CAP" => Some("20".to_string()),
+        _ => None,
+    });
+    let author = app.user("author", Role::User).await;
+    let points = &app.config.reputation;
+
+    // Votes on different posts don't contend for a row, only for the cap.
+    let mut votes = Vec::new();
+    for i in 0..8 {
+        let voter = app.user(&format!("voter{}", i), Role::User).await;
+        let target = VoteTarget::Question(app.question(&author).await);
+        votes.push((voter, target));
+    }
+
+    // One upvote short of the cap, then several at once, on connections
+    // that are already open so that the votes overlap.
+    for (voter, target) in &votes[..3] {
+        app.store.vote(voter.id, *target, 1, points).await.unwrap();
+    }
+    // The test pool holds five connections.
+    let warm = futures_util::future::join_all((3..8).map(|_| pool.acquire())).await;
+    drop(warm);
+    let results = futures_util::future::join_all(
+        votes[3..]
+            .iter()
+            .map(|(voter, target)| app.store.vote(voter.id, *target, 1, points)),
+    )
+    .await;
+
+    for result in results {
+        result.unwrap();
+    }
+    assert_eq!(app.store.get_reputation(author.id).await.unwrap(), 20);
+}
+
+#[sqlx::test(migrator = "askly::store::MIGRATOR")]
+async fn votes_and_accepts_are_published(pool: PgPool) {
+    let app = App::new(pool.clone());
+    let author = app.user("author", Role::User).await;
+    let answerer = app.user("answerer", Role::User).await;
+    let question = app.question(&author).await;
+    let answer = app.answer(&answerer, question).await;
+
+    let mut listener = PgListener::connect_with(&pool).await.unwrap();
+    listener.listen(EVENTS_CHANNEL).await.unwrap();
+
+    let vote = format!("/v1/answers/{}/vote", answer);
+    let response = app
+        .request("PUT", &vote, Some(&author), Some(json!({ "value": 1 })))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    let response = app.request("DELETE", &vote, Some(&author), None).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    let accept = format!("/v1/answers/{}/accept", answer);
+    let response = app.request("PUT", &accept, Some(&author), None).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    let response = app.request("DELETE", &accept, Some(&author), None).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    let mut changes = Vec::new();
+    for _ in 0..4 {
+        let notification = listener.recv().await.unwrap();
+        changes.push(serde_json::from_str::<Change>(notification.payload()).unwrap());
+    }
+
+    let voted = |change: &Change| {
+        matches!(change, Change::Voted { question_id, answer_id }
+            if *question_id == question && *answer_id == Some(answer))
+    };
+    assert!(voted(&changes[0]));
+    assert!(voted(&changes[1]));
+    assert!(
+        matches!(changes[2], Change::AnswerAccepted { question_id, answer_id }
+        if question_id == question && answer_id == answer)
+    );
+    assert!(
+        matches!(changes[3], Change::AnswerUnaccepted { question_id, answer_id }
+        if question_id == question && answer_id == answer)
+    );
+}
+
+#[sqlx::test(migrator = "askly::store::MIGRATOR")]
+async fn accepting_notifies_the_answer_author(pool: PgPool) {
+    let app = App::new(pool);
+    let author = app.user("author", Role::User).await;
+    let answerer = app.user("answerer", Role::User).await;
+    let quiet = app.user("quiet", Role::User).await;
+    let question = app.question(&author).await;
+    let answer = app.answer(&answerer, question).await;
+    let quiet_answer = app.answer(&quiet, question).await;
+
+    let preferences = json!({ "accepted": false });
+    let response = app
+        .request(
+            "PUT",
+            "/v1/me/notification-preferences",
+            Some(&quiet),
+            Some(preferences),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert_eq!(response.body, json!({ "answer": true, "accepted": false }));
+
+    for answer in [quiet_answer, answer] {
+        let accept = format!("/v1/answers/{}/accept", answer);
+        let response = app.request("PUT", &accept, Some(&author), None).await;
+        assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    }
+
+    let response = app
+        .request("GET", "/v1/me/notifications", Some(&answerer), None)
+        .await;
+    let notifications = &response.body["notifications"];
+    assert_eq!(notifications.as_array().unwrap().len(), 1);
+    assert_eq!(notifications[0]["kind"], "accepted");
+    assert_eq!(notifications[0]["answer_id"], answer.to_string());
+    assert_eq!(notifications[0]["actor_id"], author.id.to_string());
+
+    let response = app
+        .request("GET", "/v1/me/notifications", Some(&quiet), None)
+        .await;
+    assert_eq!(response.body["notifications"], json!([]));
+}
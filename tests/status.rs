@@ -0,0 +1,52 @@
+mod common;
+
+use askly::domain::user::Role;
+use serde_json::json;
+use sqlx::PgPool;
+use warp::http::StatusCode;
+
+use common::App;
+
+#[sqlx::test(migrator = "askly::store::MIGRATOR")]
+async fn moderator_closes_and_reopens_a_question(pool: PgPool) {
+    let app = App::new(pool);
+    let author = app.user("author", Role::User).await;
+    let moderator = app.user("moderator", Role::Moderator).await;
+    let id = app.question(&author).await;
+    let path = format!("/v1/questions/{}/status", id);
+
+    let close = json!({ "status": "closed", "reason": "off_topic" });
+    let response = app
+        .request("PUT", &path, Some(&moderator), Some(close))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert_eq!(response.body["status"], "closed");
+    assert_eq!(response.body["close_reason"], "off_topic");
+
+    let reopen = json!({ "status": "open" });
+    let response = app
+        .request("PUT", &path, Some(&moderator), Some(reopen))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert_eq!(response.body["status"], "open");
+    assert!(response.body.get("close_reason").is_none());
+}
+
+#[sqlx::test(migrator = "askly::store::MIGRATOR")]
+async fn users_cannot_set_the_status(pool: PgPool) {
+    let app = App::new(pool);
+    let author = app.user("author", Role::User).await;
+    let id = app.question(&author).await;
+
+    let close = json!({ "status": "closed", "reason": "off_topic" });
+    let response = app
+        .request(
+            "PUT",
+            &format!("/v1/questions/{}/status", id),
+            Some(&author),
+            Some(close),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::FORBIDDEN);
+    assert_eq!(response.body["message"], "Only moderators may do this");
+}
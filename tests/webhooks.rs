@@ -0,0 +1,128 @@
+mod common;
+
+use std::time::Duration;
+
+use askly::{
+    domain::user::Role,
+    shutdown::Shutdown,
+    webhooks::{self, Received},
+};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+use warp::http::StatusCode;
+
+use common::{App, User};
+
+const SECRET: &str = "webhook-secret";
+
+/// Starts a receiver answering 500 to the first `fail` deliveries and
+/// subscribes a webhook to it.
+async fn subscribe(
+    app: &App,
+    admin: &User,
+    fail: usize,
+) -> (String, mpsc::UnboundedReceiver<Received>) {
+    let (sink, received) = mpsc::unbounded_channel();
+    let (addr, server) = warp::serve(webhooks::receiver(SECRET.to_string(), fail, sink))
+        .bind_ephemeral(([127, 0, 0, 1], 0));
+    tokio::spawn(server);
+
+    let webhook = json!({
+        "url": format!("http://{}/hook", addr),
+        "events": ["question.created"],
+        "secret": SECRET,
+    });
+    let response = app
+        .request("POST", "/v1/webhooks", Some(admin), Some(webhook))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    let id = response.body["id"].as_str().unwrap().to_string();
+    (id, received)
+}
+
+async fn next(received: &mut mpsc::UnboundedReceiver<Received>) -> Received {
+    tokio::time::timeout(Duration::from_secs(10), received.recv())
+        .await
+        .expect("a delivery within 10s")
+        .expect("receiver running")
+}
+
+/// The webhook's latest delivery once the worker has recorded an attempt.
+async fn recorded(app: &App, admin: &User, webhook_id: &str) -> Value {
+    let path = format!("/v1/webhooks/{}/deliveries", webhook_id);
+    for _ in 0..50 {
+        let response = app.request("GET", &path, Some(admin), None).await;
+        assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+        let delivery = &response.body[0];
+        if delivery["attempts"].as_i64() > Some(0) {
+            return delivery.clone();
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    panic!("delivery was never recorded");
+}
+
+#[sqlx::test(migrator = "askly::store::MIGRATOR")]
+async fn queued_events_reach_the_receiver_signed(pool: PgPool) {
+    let app = App::new(pool);
+    let admin = app.user("admin", Role::Admin).await;
+    let (webhook_id, mut received) = subscribe(&app, &admin, 0).await;
+    let question_id = app.question(&admin).await;
+
+    let (trigger, shutdown) = Shutdown::new();
+    let worker = webhooks::spawn(app.store.clone(), shutdown);
+
+    let delivery = next(&mut received).await;
+    assert!(delivery.verified);
+    assert_eq!(delivery.event, "question.created");
+    assert_eq!(delivery.status, StatusCode::NO_CONTENT);
+    let payload: Value = serde_json::from_str(&delivery.body).unwrap();
+    assert_eq!(payload["event"], "question.created");
+    assert_eq!(payload["data"]["id"], question_id.to_string());
+
+    let recorded = recorded(&app, &admin, &webhook_id).await;
+    assert_eq!(recorded["id"], delivery.delivery);
+    assert_eq!(recorded["status"], "delivered");
+    assert_eq!(recorded["attempts"], 1);
+    assert_eq!(recorded["response_status"], 204);
+
+    trigger.fire();
+    worker.await.unwrap();
+}
+
+#[sqlx::test(migrator = "askly::store::MIGRATOR")]
+async fn failed_deliveries_are_retried_later(pool: PgPool) {
+    let app = App::new(pool);
+    let admin = app.user("admin", Role::Admin).await;
+    let (webhook_id, mut received) = subscribe(&app, &admin, 1).await;
+    app.question(&admin).await;
+
+    let (trigger, shutdown) = Shutdown::new();
+    let worker = webhooks::spawn(app.store.clone(), shutdown);
+
+    let delivery = next(&mut received).await;
+    assert_eq!(delivery.status, StatusCode::INTERNAL_SERVER_ERROR);
+
+    let recorded = recorded(&app, &admin, &webhook_id).await;
+    assert_eq!(recorded["status"], "pending");
+    assert_eq!(recorded["attempts"], 1);
+    assert_eq!(recorded["response_status"], 500);
+
+    // The first retry is due 10s after the failed attempt.
+    let id: Uuid = delivery.delivery.parse().unwrap();
+    let delay: f64 = sqlx::query_scalar(
+        "SELECT EXTRACT(EPOCH FROM next_attempt_on - NOW())::float8 FROM webhook_deliveries WHERE id = $1",
+    )
+    .bind(id)
+    .fetch_one(&app.store.conn)
+    .await
+    .unwrap();
+    assert!((5.0..=10.0).contains(&delay), "retry due in {}s", delay);
+
+    trigger.fire();
+    worker.await.unwrap();
+}